@@ -0,0 +1,57 @@
+//! Compares the counting sort fast path with `sort_unstable`.
+//!
+//! Run with `cargo bench --bench sort`, optionally passing a row count:
+//! `cargo bench --bench sort -- 1000000`. Defaults to 10M rows.
+
+use day_1_historian_historia::rng::Rng;
+use day_1_historian_historia::sort::{choose_strategy, sort_with, SortStrategy};
+use std::time::{Duration, Instant};
+
+fn random(n: usize, modulus: u64, seed: u64) -> Vec<i64> {
+    let mut rng = Rng::new(seed);
+    (0..n).map(|_| rng.below(modulus) as i64).collect()
+}
+
+fn time(values: &[i64], strategy: SortStrategy) -> (Duration, Vec<i64>) {
+    let mut v = values.to_vec();
+    let start = Instant::now();
+    sort_with(&mut v, strategy);
+    (start.elapsed(), v)
+}
+
+fn main() {
+    let rows = std::env::args()
+        .skip(1)
+        .find_map(|a| a.parse().ok())
+        .unwrap_or(10_000_000);
+
+    let inputs = [
+        ("five-digit ids", random(rows, 90_000, 1)),
+        ("30-bit range", random(rows, 1 << 30, 2)),
+        ("40-bit range", random(rows, 1 << 40, 3)),
+    ];
+
+    println!("{} rows", rows);
+    for (label, values) in &inputs {
+        let (baseline, expected) = time(values, SortStrategy::Comparison);
+        print!(
+            "{:<15} chosen {:<10}",
+            label,
+            format!("{:?}", choose_strategy(values))
+        );
+        print!("  sort_unstable {:>9.1?}", baseline);
+
+        if choose_strategy(values) == SortStrategy::Counting {
+            let strategy = SortStrategy::Counting;
+            let (elapsed, sorted) = time(values, strategy);
+            assert_eq!(sorted, expected, "{} {:?} mismatch", label, strategy);
+            print!(
+                "  {:?} {:>9.1?} ({:.2}x)",
+                strategy,
+                elapsed,
+                baseline.as_secs_f64() / elapsed.as_secs_f64()
+            );
+        }
+        println!();
+    }
+}
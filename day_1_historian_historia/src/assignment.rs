@@ -0,0 +1,224 @@
+//! Minimum-cost bipartite assignment between the two columns.
+//!
+//! Pairing the sorted columns is optimal only for convex, order-preserving
+//! costs such as `|a - b|`. For anything else (asymmetric penalties, a per-pair
+//! cost table) the Hungarian algorithm below finds the true optimum in O(n^3).
+
+use crate::error::Error;
+use crate::metric::Metric;
+use crate::{create_sorted_tuples, sum_differences};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment {
+    /// `(i, j)` pairs: row `i` of the left column is matched to row `j` of the
+    /// right column. Sorted by `i`.
+    pub pairs: Vec<(usize, usize)>,
+    pub total_cost: f64,
+}
+
+/// Solves the assignment for an `n x n` cost table, `cost[i][j]` being the cost
+/// of matching left row `i` with right row `j`. The table must be square and
+/// every cost finite.
+pub fn assign_with_table(cost: &[Vec<f64>]) -> Result<Assignment, Error> {
+    let n = cost.len();
+    if let Some(row) = cost.iter().find(|row| row.len() != n) {
+        return Err(Error::ColumnMismatch {
+            first: n,
+            second: row.len(),
+        });
+    }
+    hungarian(n, |i, j| cost[i][j])
+}
+
+/// Solves the assignment with `metric.distance(first[i], second[j])` as the
+/// cost of each pair. For the L1 metric the result is checked against the
+/// sorted pairing, which is known to be optimal there; disagreement means the
+/// solver is broken, and is returned as an error rather than a wrong answer.
+pub fn assign_with_metric(
+    first: &[i64],
+    second: &[i64],
+    metric: &dyn Metric,
+) -> Result<Assignment, Error> {
+    if first.len() != second.len() {
+        return Err(Error::ColumnMismatch {
+            first: first.len(),
+            second: second.len(),
+        });
+    }
+    let assignment = hungarian(first.len(), |i, j| metric.distance(first[i], second[j]))?;
+
+    if metric.is_l1() {
+        // The check is skipped if the exact total overflows u64, in which case
+        // the f64 cost cannot be compared exactly anyway.
+        let tuples = create_sorted_tuples(first.to_vec(), second.to_vec());
+        if let Ok(sorted) = sum_differences(&tuples) {
+            let tolerance = 1e-9 * (sorted as f64).max(1.0);
+            if (assignment.total_cost - sorted as f64).abs() > tolerance {
+                return Err(Error::AssignmentMismatch {
+                    cost: assignment.total_cost,
+                    sorted,
+                });
+            }
+        }
+    }
+
+    Ok(assignment)
+}
+
+/// Hungarian algorithm with row/column potentials (Kuhn-Munkres, shortest
+/// augmenting path form). Indices are 1-based internally; 0 is a sentinel.
+///
+/// Costs are checked up front: with an infinite or NaN cost no augmenting step
+/// can make progress, and the search below would never terminate.
+fn hungarian<F: Fn(usize, usize) -> f64>(n: usize, cost: F) -> Result<Assignment, Error> {
+    for row in 0..n {
+        if let Some(column) = (0..n).find(|&column| !cost(row, column).is_finite()) {
+            return Err(Error::NonFiniteCost { row, column });
+        }
+    }
+
+    let mut u = vec![0.0; n + 1];
+    let mut v = vec![0.0; n + 1];
+    // p[j]: left row currently matched to right column j.
+    let mut p = vec![0usize; n + 1];
+    let mut way = vec![0usize; n + 1];
+
+    for i in 1..=n {
+        p[0] = i;
+        let mut j0 = 0;
+        let mut minv = vec![f64::INFINITY; n + 1];
+        let mut used = vec![false; n + 1];
+
+        loop {
+            used[j0] = true;
+            let i0 = p[j0];
+            let mut delta = f64::INFINITY;
+            let mut j1 = 0;
+            for j in 1..=n {
+                if used[j] {
+                    continue;
+                }
+                let cur = cost(i0 - 1, j - 1) - u[i0] - v[j];
+                if cur < minv[j] {
+                    minv[j] = cur;
+                    way[j] = j0;
+                }
+                if minv[j] < delta {
+                    delta = minv[j];
+                    j1 = j;
+                }
+            }
+            for j in 0..=n {
+                if used[j] {
+                    u[p[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    minv[j] -= delta;
+                }
+            }
+            j0 = j1;
+            if p[j0] == 0 {
+                break;
+            }
+        }
+
+        while j0 != 0 {
+            let j1 = way[j0];
+            p[j0] = p[j1];
+            j0 = j1;
+        }
+    }
+
+    let mut pairs: Vec<(usize, usize)> = (1..=n).map(|j| (p[j] - 1, j - 1)).collect();
+    pairs.sort_unstable();
+    let total_cost = pairs.iter().map(|&(i, j)| cost(i, j)).sum();
+    Ok(Assignment { pairs, total_cost })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metric::{BuiltinMetric, FnMetric};
+
+    #[test]
+    fn test_table_assignment() {
+        let cost = vec![
+            vec![4.0, 1.0, 3.0],
+            vec![2.0, 0.0, 5.0],
+            vec![3.0, 2.0, 2.0],
+        ];
+        let assignment = assign_with_table(&cost).unwrap();
+        assert_eq!(assignment.pairs, vec![(0, 1), (1, 0), (2, 2)]);
+        assert_eq!(assignment.total_cost, 5.0);
+    }
+
+    #[test]
+    fn test_l1_matches_sorted_pairing() {
+        let first = [3, 4, 2, 1, 3, 3];
+        let second = [4, 3, 5, 3, 9, 3];
+        let assignment = assign_with_metric(&first, &second, &BuiltinMetric::Absolute).unwrap();
+        assert_eq!(assignment.total_cost, 11.0);
+    }
+
+    #[test]
+    fn test_concave_cost_beats_sorted_pairing() {
+        // With distances capped at 2 the sorted pairing (0->5, 5->10) costs 4,
+        // while matching the exact 5->5 pair and sacrificing the other costs 2.
+        let first = [0, 5];
+        let second = [5, 10];
+        let assignment = assign_with_metric(&first, &second, &BuiltinMetric::Capped(2.0)).unwrap();
+        assert_eq!(assignment.pairs, vec![(0, 1), (1, 0)]);
+        assert_eq!(assignment.total_cost, 2.0);
+    }
+
+    #[test]
+    fn test_custom_metric_assignment() {
+        let exact = FnMetric::new("mismatch", |a, b| if a == b { 0.0 } else { 1.0 });
+        let assignment = assign_with_metric(&[7, 3, 9], &[9, 7, 4], &exact).unwrap();
+        assert_eq!(assignment.pairs, vec![(0, 1), (1, 2), (2, 0)]);
+        assert_eq!(assignment.total_cost, 1.0);
+    }
+
+    #[test]
+    fn test_l1_check_ignores_metric_name() {
+        // Named like the built-in, but not L1: must not be held to the sorted
+        // pairing.
+        let sqrt = FnMetric::new("abs", |a, b| (a.abs_diff(b) as f64).sqrt());
+        let assignment = assign_with_metric(&[0, 10], &[10, 20], &sqrt).unwrap();
+        assert_eq!(assignment.pairs, vec![(0, 1), (1, 0)]);
+        assert!(assignment.total_cost < 20.0);
+    }
+
+    #[test]
+    fn test_non_finite_cost() {
+        let exact = FnMetric::new("exact", |a, b| if a == b { 0.0 } else { f64::INFINITY });
+        assert!(matches!(
+            assign_with_metric(&[1, 2], &[2, 3], &exact),
+            Err(Error::NonFiniteCost { row: 0, column: 0 })
+        ));
+        let cost = vec![vec![1.0, f64::NAN], vec![0.0, 2.0]];
+        assert!(matches!(
+            assign_with_table(&cost),
+            Err(Error::NonFiniteCost { row: 0, column: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_shape_errors() {
+        let cost = vec![vec![1.0, 2.0], vec![3.0]];
+        assert!(matches!(
+            assign_with_table(&cost),
+            Err(Error::ColumnMismatch {
+                first: 2,
+                second: 1
+            })
+        ));
+        assert!(matches!(
+            assign_with_metric(&[1, 2, 3], &[1], &BuiltinMetric::Absolute),
+            Err(Error::ColumnMismatch {
+                first: 3,
+                second: 1
+            })
+        ));
+    }
+}
@@ -0,0 +1,145 @@
+//! Multiset difference between the two location columns, for reconciling
+//! historians that disagree.
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountDiff {
+    pub value: i64,
+    pub left: usize,
+    pub right: usize,
+}
+
+/// IDs present in one column only, and IDs present in both but a different
+/// number of times. Each list is sorted by ID.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MultisetDiff {
+    pub only_left: Vec<(i64, usize)>,
+    pub only_right: Vec<(i64, usize)>,
+    pub different: Vec<CountDiff>,
+}
+
+fn counts(values: &[i64]) -> BTreeMap<i64, usize> {
+    let mut counts = BTreeMap::new();
+    for &v in values {
+        *counts.entry(v).or_insert(0) += 1;
+    }
+    counts
+}
+
+impl MultisetDiff {
+    pub fn new(first: &[i64], second: &[i64]) -> MultisetDiff {
+        let left = counts(first);
+        let mut right = counts(second);
+        let mut diff = MultisetDiff::default();
+
+        for (value, l) in left {
+            match right.remove(&value) {
+                None => diff.only_left.push((value, l)),
+                Some(r) if r != l => diff.different.push(CountDiff {
+                    value,
+                    left: l,
+                    right: r,
+                }),
+                Some(_) => {}
+            }
+        }
+        diff.only_right = right.into_iter().collect();
+        diff
+    }
+
+    /// True when both columns hold exactly the same IDs the same number of times.
+    pub fn is_empty(&self) -> bool {
+        self.only_left.is_empty() && self.only_right.is_empty() && self.different.is_empty()
+    }
+
+    pub fn write_text<W: Write>(&self, mut out: W) -> io::Result<()> {
+        for (label, only) in [("left", &self.only_left), ("right", &self.only_right)] {
+            let entries: usize = only.iter().map(|&(_, n)| n).sum();
+            writeln!(
+                out,
+                "only in {} ({} ids, {} entries):",
+                label,
+                only.len(),
+                entries
+            )?;
+            for (value, n) in only {
+                writeln!(out, "  {} x{}", value, n)?;
+            }
+        }
+        writeln!(out, "different counts ({} ids):", self.different.len())?;
+        for d in &self.different {
+            writeln!(out, "  {} left={} right={}", d.value, d.left, d.right)?;
+        }
+        out.flush()
+    }
+
+    pub fn write_json<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let only = |list: &[(i64, usize)]| {
+            list.iter()
+                .map(|(value, n)| format!(r#"{{"value":{},"count":{}}}"#, value, n))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let different = self
+            .different
+            .iter()
+            .map(|d| {
+                format!(
+                    r#"{{"value":{},"left":{},"right":{}}}"#,
+                    d.value, d.left, d.right
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        writeln!(
+            out,
+            r#"{{"only_left":[{}],"only_right":[{}],"different":[{}]}}"#,
+            only(&self.only_left),
+            only(&self.only_right),
+            different
+        )?;
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multiset_diff() {
+        let diff = MultisetDiff::new(&[3, 4, 2, 1, 3, 3], &[4, 3, 5, 3, 9, 3, 4]);
+        assert_eq!(diff.only_left, vec![(1, 1), (2, 1)]);
+        assert_eq!(diff.only_right, vec![(5, 1), (9, 1)]);
+        assert_eq!(
+            diff.different,
+            vec![CountDiff {
+                value: 4,
+                left: 1,
+                right: 2
+            }]
+        );
+        assert!(!diff.is_empty());
+        assert!(MultisetDiff::new(&[1, 2, 2], &[2, 1, 2]).is_empty());
+
+        let mut json = Vec::new();
+        diff.write_json(&mut json).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            concat!(
+                r#"{"only_left":[{"value":1,"count":1},{"value":2,"count":1}],"#,
+                r#""only_right":[{"value":5,"count":1},{"value":9,"count":1}],"#,
+                r#""different":[{"value":4,"left":1,"right":2}]}"#,
+                "\n"
+            )
+        );
+
+        let mut text = Vec::new();
+        diff.write_text(&mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.starts_with("only in left (2 ids, 2 entries):\n  1 x1\n  2 x1\n"));
+        assert!(text.ends_with("different counts (1 ids):\n  4 left=1 right=2\n"));
+    }
+}
@@ -0,0 +1,144 @@
+use std::fmt;
+use std::io;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    InvalidNumber,
+    TooFewColumns { expected: usize, found: usize },
+    TooManyColumns { expected: usize, found: usize },
+}
+
+/// A rejected input line, pointing at the token that caused the rejection.
+/// `column` is a byte offset into `line_text`; for missing columns it points
+/// just past the end of the line and `text` is empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub line_text: String,
+}
+
+impl ParseError {
+    /// Renders the offending line with the bad token underlined, e.g.
+    ///
+    /// ```text
+    ///   3 | 12    1x4
+    ///     |       ^^^
+    /// ```
+    pub fn snippet(&self) -> String {
+        let gutter = self.line.to_string();
+        let pad = " ".repeat(gutter.len());
+        let lead: String = self.line_text[..self.column]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let carets = "^".repeat(self.text.chars().count().max(1));
+        format!(
+            " {} | {}\n {} | {}{}",
+            gutter, self.line_text, pad, lead, carets
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column + 1)?;
+        match &self.kind {
+            ParseErrorKind::InvalidNumber => write!(f, "unable to parse number `{}`", self.text),
+            ParseErrorKind::TooFewColumns { expected, found } => {
+                write!(f, "expecting {} numbers, found {}", expected, found)
+            }
+            ParseErrorKind::TooManyColumns { expected, found } => write!(
+                f,
+                "expecting {} numbers, found {} (first extra is `{}`)",
+                expected, found, self.text
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug)]
+pub enum Error {
+    Open(io::Error),
+    Read {
+        line: usize,
+        source: io::Error,
+    },
+    Parse(ParseError),
+    Spill(io::Error),
+    /// An accumulated total no longer fits in 64 bits.
+    Overflow(&'static str),
+    NoSuchColumn {
+        index: usize,
+        width: usize,
+    },
+    ColumnMismatch {
+        first: usize,
+        second: usize,
+    },
+    /// The optimal L1 assignment disagrees with the sorted pairing, which is
+    /// known to be optimal for L1.
+    AssignmentMismatch {
+        cost: f64,
+        sorted: u64,
+    },
+    /// A pairing cost is infinite or NaN, so no assignment can be optimal.
+    NonFiniteCost {
+        row: usize,
+        column: usize,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Open(e) => write!(f, "file cannot be opened: {}", e),
+            Error::Read { line, source } => write!(f, "failed to read line {}: {}", line, source),
+            Error::Parse(e) => write!(f, "{}\n{}", e, e.snippet()),
+            Error::Spill(e) => write!(f, "failed to spill sorted run: {}", e),
+            Error::Overflow(what) => write!(f, "{} overflows the 64-bit range", what),
+            Error::NoSuchColumn { index, width } => {
+                write!(f, "no column {} in a table of width {}", index, width)
+            }
+            Error::ColumnMismatch { first, second } => write!(
+                f,
+                "expected to match number count in columns: first={}, second={}",
+                first, second
+            ),
+            Error::AssignmentMismatch { cost, sorted } => write!(
+                f,
+                "assignment cost {} disagrees with sorted pairing {}",
+                cost, sorted
+            ),
+            Error::NonFiniteCost { row, column } => write!(
+                f,
+                "cost of pairing row {} with row {} is not a finite number",
+                row, column
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Open(e) | Error::Read { source: e, .. } | Error::Spill(e) => Some(e),
+            Error::Parse(e) => Some(e),
+            Error::Overflow(_)
+            | Error::NoSuchColumn { .. }
+            | Error::ColumnMismatch { .. }
+            | Error::AssignmentMismatch { .. }
+            | Error::NonFiniteCost { .. } => None,
+        }
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Error::Parse(e)
+    }
+}
@@ -0,0 +1,265 @@
+//! Out-of-core sorting for location lists that do not fit in memory.
+//!
+//! Each column is read in chunks of at most `run_len` values; every chunk is
+//! sorted and spilled to its own temp file as a "run". The runs of a column are
+//! then k-way merged, and the two merged columns are walked in lockstep so the
+//! pairs can be accumulated without ever materialising the full lists.
+//!
+//! A merge holds one open file per run. To stay well under the usual limit of
+//! 1024 open files, runs are first merged in groups of at most
+//! [`MAX_MERGE_WIDTH`] into longer runs until that few remain per column.
+
+use crate::error::{Error, ParseError};
+use crate::{add_distance, calculate_abs_diff, process_line, Ingestion};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_RUN: AtomicUsize = AtomicUsize::new(0);
+
+/// Most runs of one column that are open at once during a merge.
+pub const MAX_MERGE_WIDTH: usize = 64;
+
+fn run_path() -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "historian-run-{}-{}",
+        std::process::id(),
+        NEXT_RUN.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// Sorted runs of one column on disk. The files are removed on drop.
+#[derive(Debug, Default)]
+struct Runs {
+    paths: Vec<PathBuf>,
+}
+
+impl Runs {
+    fn spill(&mut self, chunk: &mut Vec<i64>) -> io::Result<()> {
+        if chunk.is_empty() {
+            return Ok(());
+        }
+        chunk.sort_unstable();
+
+        let path = run_path();
+        // Track the path before writing so a failed write is still cleaned up.
+        self.paths.push(path.clone());
+        let mut writer = BufWriter::new(File::create(&path)?);
+        for n in chunk.iter() {
+            writer.write_all(&n.to_le_bytes())?;
+        }
+        writer.flush()?;
+        chunk.clear();
+        Ok(())
+    }
+
+    /// Merges the oldest `width` runs into one new run, repeatedly, until at
+    /// most `width` runs are left. Merged-away files are removed as it goes.
+    fn reduce(&mut self, width: usize) -> io::Result<()> {
+        let width = width.max(2);
+        while self.paths.len() > width {
+            let group = Runs {
+                paths: self.paths.drain(..width).collect(),
+            };
+            let path = run_path();
+            self.paths.push(path.clone());
+            let mut writer = BufWriter::new(File::create(&path)?);
+            for n in group.merge()? {
+                writer.write_all(&n?.to_le_bytes())?;
+            }
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn merge(&self) -> io::Result<Merge> {
+        let mut readers = Vec::with_capacity(self.paths.len());
+        let mut heap = BinaryHeap::with_capacity(self.paths.len());
+        for (i, path) in self.paths.iter().enumerate() {
+            let mut reader = BufReader::new(File::open(path)?);
+            if let Some(n) = read_value(&mut reader)? {
+                heap.push(Reverse((n, i)));
+            }
+            readers.push(reader);
+        }
+        Ok(Merge { readers, heap })
+    }
+}
+
+impl Drop for Runs {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn read_value<R: Read>(reader: &mut R) -> io::Result<Option<i64>> {
+    let mut buf = [0u8; 8];
+    match reader.read_exact(&mut buf) {
+        Ok(()) => Ok(Some(i64::from_le_bytes(buf))),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// K-way merge over the runs of one column, yielding values in ascending order.
+struct Merge {
+    readers: Vec<BufReader<File>>,
+    heap: BinaryHeap<Reverse<(i64, usize)>>,
+}
+
+impl Iterator for Merge {
+    type Item = io::Result<i64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((n, i)) = self.heap.pop()?;
+        match read_value(&mut self.readers[i]) {
+            Ok(Some(next)) => self.heap.push(Reverse((next, i))),
+            Ok(None) => {}
+            Err(e) => return Some(Err(e)),
+        }
+        Some(Ok(n))
+    }
+}
+
+/// Computes the same total distance as `sum_differences(&create_sorted_tuples(..))`
+/// while holding at most `run_len` values per column in memory. Under
+/// [`Ingestion::Lenient`] bad lines are skipped and returned.
+pub fn external_sum_differences<R: BufRead>(
+    reader: R,
+    run_len: usize,
+    mode: Ingestion,
+) -> Result<(u64, Vec<ParseError>), Error> {
+    sum_with_merge_width(reader, run_len, mode, MAX_MERGE_WIDTH)
+}
+
+fn sum_with_merge_width<R: BufRead>(
+    reader: R,
+    run_len: usize,
+    mode: Ingestion,
+    merge_width: usize,
+) -> Result<(u64, Vec<ParseError>), Error> {
+    let run_len = run_len.max(1);
+    let (mut first_runs, mut second_runs) = (Runs::default(), Runs::default());
+    let mut first = Vec::with_capacity(run_len);
+    let mut second = Vec::with_capacity(run_len);
+    let mut line_count = 0;
+    let mut rejected = Vec::new();
+
+    for line in reader.lines() {
+        line_count += 1;
+        let line = line.map_err(|source| Error::Read {
+            line: line_count,
+            source,
+        })?;
+        let (a, b) = match process_line(&line, line_count) {
+            Ok(pair) => pair,
+            Err(e) if mode == Ingestion::Lenient => {
+                rejected.push(e);
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        first.push(a);
+        second.push(b);
+
+        if first.len() == run_len {
+            first_runs.spill(&mut first).map_err(Error::Spill)?;
+            second_runs.spill(&mut second).map_err(Error::Spill)?;
+        }
+    }
+    first_runs.spill(&mut first).map_err(Error::Spill)?;
+    second_runs.spill(&mut second).map_err(Error::Spill)?;
+    first_runs.reduce(merge_width).map_err(Error::Spill)?;
+    second_runs.reduce(merge_width).map_err(Error::Spill)?;
+
+    let mut sum = 0;
+    let pairs = first_runs
+        .merge()
+        .map_err(Error::Spill)?
+        .zip(second_runs.merge().map_err(Error::Spill)?);
+    for (a, b) in pairs {
+        let pair = (a.map_err(Error::Spill)?, b.map_err(Error::Spill)?);
+        sum = add_distance(sum, calculate_abs_diff(&pair))?;
+    }
+    Ok((sum, rejected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::{create_sorted_tuples, sum_differences};
+
+    #[test]
+    fn test_matches_in_memory_path() {
+        let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+        let mut next = || rng.below(200_000) as i64 - 100_000;
+        let rows: Vec<(i64, i64)> = (0..1_003).map(|_| (next(), next())).collect();
+        let input: String = rows
+            .iter()
+            .map(|(a, b)| format!("{}   {}\n", a, b))
+            .collect();
+
+        let (first, second) = rows.iter().copied().unzip();
+        let expected = sum_differences(&create_sorted_tuples(first, second)).unwrap();
+        for run_len in [1, 7, 100, 5_000] {
+            let sum = external_sum_differences(input.as_bytes(), run_len, Ingestion::Strict)
+                .unwrap()
+                .0;
+            assert_eq!(sum, expected, "run_len={}", run_len);
+        }
+    }
+
+    #[test]
+    fn test_more_runs_than_merge_width() {
+        let mut rng = Rng::new(0x9e37_79b9_7f4a_7c15);
+        let rows = 3 * MAX_MERGE_WIDTH + 5;
+        let input: String = (0..rows)
+            .map(|_| format!("{} {}\n", rng.below(1_000), rng.below(1_000)))
+            .collect();
+        let expected = external_sum_differences(input.as_bytes(), rows, Ingestion::Strict)
+            .unwrap()
+            .0;
+
+        // One run per row, so every merge level is needed.
+        assert_eq!(
+            external_sum_differences(input.as_bytes(), 1, Ingestion::Strict)
+                .unwrap()
+                .0,
+            expected
+        );
+        for width in [2, 3, 10] {
+            let (sum, _) =
+                sum_with_merge_width(input.as_bytes(), 1, Ingestion::Strict, width).unwrap();
+            assert_eq!(sum, expected, "width={}", width);
+        }
+
+        let mut runs = Runs::default();
+        for n in 0..10 {
+            runs.spill(&mut vec![n]).unwrap();
+        }
+        runs.reduce(3).unwrap();
+        assert!(runs.paths.len() <= 3);
+        let merged: Vec<i64> = runs.merge().unwrap().map(Result::unwrap).collect();
+        assert_eq!(merged, (0..10).collect::<Vec<i64>>());
+    }
+
+    #[test]
+    fn test_lenient_skips_bad_lines() {
+        let input = "3 4\nx 3\n4 3\n2 5 7\n1 3\n";
+        assert!(matches!(
+            external_sum_differences(input.as_bytes(), 2, Ingestion::Strict),
+            Err(Error::Parse(_))
+        ));
+        let (sum, rejected) =
+            external_sum_differences(input.as_bytes(), 2, Ingestion::Lenient).unwrap();
+        assert_eq!(sum, 2);
+        let lines: Vec<usize> = rejected.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![2, 4]);
+    }
+}
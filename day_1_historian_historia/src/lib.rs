@@ -0,0 +1,304 @@
+pub mod assignment;
+pub mod diff;
+pub mod error;
+pub mod external_sort;
+pub mod metric;
+pub mod report;
+pub mod rng;
+pub mod sort;
+pub mod stats;
+pub mod stream;
+pub mod table;
+
+use error::{Error, ParseError, ParseErrorKind};
+use metric::Metric;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::mem;
+use std::path::Path;
+
+/// Splits a line on whitespace, keeping the byte offset of each token.
+fn tokens(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.split_whitespace()
+        .map(move |s| (s.as_ptr() as usize - line.as_ptr() as usize, s))
+}
+
+pub fn process_line(line: &str, line_num: usize) -> Result<(i64, i64), ParseError> {
+    let error = |kind, column, text: &str| ParseError {
+        kind,
+        line: line_num,
+        column,
+        text: text.to_string(),
+        line_text: line.to_string(),
+    };
+
+    let mut numbers = Vec::with_capacity(2);
+    for (column, token) in tokens(line) {
+        if numbers.len() == 2 {
+            let found = tokens(line).count();
+            let kind = ParseErrorKind::TooManyColumns { expected: 2, found };
+            return Err(error(kind, column, token));
+        }
+        let n = token
+            .parse::<i64>()
+            .map_err(|_| error(ParseErrorKind::InvalidNumber, column, token))?;
+        numbers.push(n);
+    }
+
+    if numbers.len() != 2 {
+        let kind = ParseErrorKind::TooFewColumns {
+            expected: 2,
+            found: numbers.len(),
+        };
+        return Err(error(kind, line.len(), ""));
+    }
+
+    Ok((numbers[0], numbers[1]))
+}
+
+/// How ingestion reacts to a malformed line. `Strict` aborts on the first one;
+/// `Lenient` drops it, records why, and carries on with the remaining rows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Ingestion {
+    #[default]
+    Strict,
+    Lenient,
+}
+
+#[derive(Debug, Default)]
+pub struct Ingested {
+    pub first: Vec<i64>,
+    pub second: Vec<i64>,
+    pub rejected: Vec<ParseError>,
+    pub line_count: usize,
+}
+
+impl Ingested {
+    /// One-line tally of dropped lines by reason.
+    pub fn summary(&self) -> String {
+        let (mut invalid, mut too_few, mut too_many) = (0, 0, 0);
+        for e in &self.rejected {
+            match e.kind {
+                ParseErrorKind::InvalidNumber => invalid += 1,
+                ParseErrorKind::TooFewColumns { .. } => too_few += 1,
+                ParseErrorKind::TooManyColumns { .. } => too_many += 1,
+            }
+        }
+        format!(
+            "dropped {} of {} lines: {} invalid number, {} too few columns, {} too many columns",
+            self.rejected.len(),
+            self.line_count,
+            invalid,
+            too_few,
+            too_many
+        )
+    }
+}
+
+pub fn read_numbers<R: BufRead>(reader: R, mode: Ingestion) -> Result<Ingested, Error> {
+    let mut ingested = Ingested::default();
+
+    for line in reader.lines() {
+        ingested.line_count += 1;
+        let line = line.map_err(|source| Error::Read {
+            line: ingested.line_count,
+            source,
+        })?;
+        match process_line(&line, ingested.line_count) {
+            Ok((first, second)) => {
+                ingested.first.push(first);
+                ingested.second.push(second);
+            }
+            Err(e) if mode == Ingestion::Lenient => ingested.rejected.push(e),
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    if ingested.first.len() != ingested.second.len() {
+        return Err(Error::ColumnMismatch {
+            first: ingested.first.len(),
+            second: ingested.second.len(),
+        });
+    }
+
+    Ok(ingested)
+}
+
+pub fn read_numbers_from_file<P: AsRef<Path>>(path: P, mode: Ingestion) -> Result<Ingested, Error> {
+    let file = File::open(path).map_err(Error::Open)?;
+    let ingested = read_numbers(BufReader::new(file), mode)?;
+
+    println!("total lines        : {}", ingested.line_count);
+    println!("first column count : {}", ingested.first.len());
+    println!("second column count: {}", ingested.second.len());
+
+    Ok(ingested)
+}
+
+pub fn create_sorted_tuples(mut first: Vec<i64>, mut second: Vec<i64>) -> Vec<(i64, i64)> {
+    sort::sort_column(&mut first);
+    sort::sort_column(&mut second);
+    first.into_iter().zip(second).collect()
+}
+
+/// The distance between two `i64` values can be as large as `u64::MAX`
+/// (`i64::MIN` to `i64::MAX`), so it is returned unsigned.
+pub fn calculate_abs_diff(&(a, b): &(i64, i64)) -> u64 {
+    a.abs_diff(b)
+}
+
+/// Adds one more distance to a running total, failing instead of wrapping.
+pub fn add_distance(total: u64, distance: u64) -> Result<u64, Error> {
+    total
+        .checked_add(distance)
+        .ok_or(Error::Overflow("total distance"))
+}
+
+pub fn sum_differences(tuples: &[(i64, i64)]) -> Result<u64, Error> {
+    tuples
+        .iter()
+        .map(calculate_abs_diff)
+        .try_fold(0, add_distance)
+}
+
+/// Scores each left-column value by how often it appears in the right column.
+/// The right column is counted once into a frequency map, so this stays linear
+/// in the number of rows.
+pub fn similarity_score(first: &[i64], second: &[i64]) -> Result<i64, Error> {
+    let mut counts: HashMap<i64, i64> = HashMap::with_capacity(second.len());
+    for &n in second {
+        *counts.entry(n).or_insert(0) += 1;
+    }
+
+    first.iter().try_fold(0i64, |total, n| {
+        n.checked_mul(counts.get(n).copied().unwrap_or(0))
+            .and_then(|score| total.checked_add(score))
+            .ok_or(Error::Overflow("similarity score"))
+    })
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Scores {
+    pub total_distance: u64,
+    pub similarity_score: i64,
+    /// Totals for any extra metrics requested, by metric name.
+    pub metric_totals: Vec<(String, f64)>,
+}
+
+/// Scores the two-column file at `path`. Also returns the ingestion record,
+/// with its columns moved out, so the caller can report rejected lines and
+/// their [`Ingested::summary`] as it sees fit.
+pub fn process_file<P: AsRef<Path>>(
+    path: P,
+    mode: Ingestion,
+    metrics: &[&dyn Metric],
+) -> Result<(Scores, Ingested), Error> {
+    let mut ingested = read_numbers_from_file(path, mode)?;
+    let (first, second) = (
+        mem::take(&mut ingested.first),
+        mem::take(&mut ingested.second),
+    );
+    let similarity_score = similarity_score(&first, &second)?;
+    let tuples = create_sorted_tuples(first, second);
+    let scores = Scores {
+        total_distance: sum_differences(&tuples)?,
+        similarity_score,
+        metric_totals: metrics
+            .iter()
+            .map(|m| m.name())
+            .zip(metric::sum_metrics(&tuples, metrics))
+            .collect(),
+    };
+    Ok((scores, ingested))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_similarity_score() {
+        let first = vec![3, 4, 2, 1, 3, 3];
+        let second = vec![4, 3, 5, 3, 9, 3];
+        assert_eq!(similarity_score(&first, &second).unwrap(), 31);
+    }
+
+    #[test]
+    fn test_sum_differences() {
+        let tuples = create_sorted_tuples(vec![3, 4, 2, 1, 3, 3], vec![4, 3, 5, 3, 9, 3]);
+        assert_eq!(sum_differences(&tuples).unwrap(), 11);
+    }
+
+    #[test]
+    fn test_extreme_distances_do_not_wrap() {
+        assert_eq!(calculate_abs_diff(&(i64::MIN, i64::MAX)), u64::MAX);
+        assert_eq!(calculate_abs_diff(&(i64::MAX, i64::MIN)), u64::MAX);
+        assert_eq!(calculate_abs_diff(&(i64::MIN, 0)), 1 << 63);
+        assert_eq!(sum_differences(&[(i64::MIN, i64::MAX)]).unwrap(), u64::MAX);
+
+        let tuples = [(i64::MIN, i64::MAX), (0, 1)];
+        assert!(matches!(
+            sum_differences(&tuples),
+            Err(Error::Overflow("total distance"))
+        ));
+    }
+
+    #[test]
+    fn test_similarity_score_overflow() {
+        assert_eq!(
+            similarity_score(&[i64::MAX], &[i64::MAX]).unwrap(),
+            i64::MAX
+        );
+        assert!(matches!(
+            similarity_score(&[i64::MAX], &[i64::MAX, i64::MAX]),
+            Err(Error::Overflow("similarity score"))
+        ));
+        assert!(matches!(
+            similarity_score(&[i64::MIN, i64::MIN], &[i64::MIN]),
+            Err(Error::Overflow("similarity score"))
+        ));
+    }
+
+    #[test]
+    fn test_process_line_invalid_number() {
+        let err = process_line("12    1x4", 3).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::InvalidNumber);
+        assert_eq!((err.line, err.column, err.text.as_str()), (3, 6, "1x4"));
+        assert_eq!(err.snippet(), " 3 | 12    1x4\n   |       ^^^");
+    }
+
+    #[test]
+    fn test_process_line_column_count() {
+        let err = process_line("1 2 3", 1).unwrap_err();
+        let kind = ParseErrorKind::TooManyColumns {
+            expected: 2,
+            found: 3,
+        };
+        assert_eq!((err.kind, err.column, err.text.as_str()), (kind, 4, "3"));
+
+        let err = process_line("7", 2).unwrap_err();
+        let kind = ParseErrorKind::TooFewColumns {
+            expected: 2,
+            found: 1,
+        };
+        assert_eq!((err.kind, err.column, err.text.as_str()), (kind, 1, ""));
+    }
+
+    #[test]
+    fn test_lenient_ingestion_skips_bad_lines() {
+        let input = "3 4\n4 x\n2 5\n1\n1 3 9\n3 3\n";
+        let err = read_numbers(input.as_bytes(), Ingestion::Strict).unwrap_err();
+        assert!(matches!(err, Error::Parse(ParseError { line: 2, .. })));
+
+        let ingested = read_numbers(input.as_bytes(), Ingestion::Lenient).unwrap();
+        assert_eq!(ingested.first, vec![3, 2, 3]);
+        assert_eq!(ingested.second, vec![4, 5, 3]);
+        let lines: Vec<usize> = ingested.rejected.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![2, 4, 5]);
+        assert_eq!(
+            ingested.summary(),
+            "dropped 3 of 6 lines: 1 invalid number, 1 too few columns, 1 too many columns"
+        );
+    }
+}
@@ -1,85 +1,292 @@
+use day_1_historian_historia::assignment::assign_with_metric;
+use day_1_historian_historia::diff::MultisetDiff;
+use day_1_historian_historia::error::{Error, ParseError};
+use day_1_historian_historia::external_sort::external_sum_differences;
+use day_1_historian_historia::metric::{BuiltinMetric, Metric};
+use day_1_historian_historia::report::{Report, ReportFormat};
+use day_1_historian_historia::stats::{histogram, sorted_distances, DistanceStats};
+use day_1_historian_historia::stream::stream_totals;
+use day_1_historian_historia::table::{Delimiter, Table};
+use day_1_historian_historia::{
+    create_sorted_tuples, process_file, read_numbers, read_numbers_from_file, Ingestion,
+};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
-
-fn process_line(line: &str, line_num: usize) -> Result<(i64, i64), String> {
-    let numbers: Vec<i64> = line
-        .split_whitespace()
-        .map(|s| {
-            s.parse::<i64>()
-                .map_err(|_| format!("unable to parse number on line {}", line_num))
-        })
-        .collect::<Result<Vec<i64>, String>>()?;
-
-    if numbers.len() != 2 {
-        return Err(format!("expecting 2 numbers on line {}", line_num));
-    }
+use std::io::{self, BufReader, Write};
+use std::str::FromStr;
 
-    Ok((numbers[0], numbers[1]))
-}
+/// Values held in memory per column before a sorted run is spilled to disk.
+const DEFAULT_RUN_LEN: usize = 1 << 20;
 
-fn read_numbers_from_file<P: AsRef<Path>>(path: P) -> Result<(Vec<i64>, Vec<i64>), String> {
-    let file = File::open(path).map_err(|e| format!("file cannot be opened: {}", e))?;
-    let reader = BufReader::new(file);
-    let mut first_numbers = Vec::new();
-    let mut second_numbers = Vec::new();
-    let mut line_count = 0;
-
-    for line in reader.lines() {
-        line_count += 1;
-        let line = line.map_err(|e| format!("failed to read line {}: {}", line_count, e))?;
-        let (first, second) = process_line(&line, line_count)?;
-        first_numbers.push(first);
-        second_numbers.push(second);
+/// Lines dropped by `--lenient` go to stderr, leaving stdout to the results.
+fn warn_rejected(rejected: &[ParseError]) {
+    for e in rejected {
+        eprintln!("skipped {}\n{}", e, e.snippet());
     }
+}
 
-    println!("total lines        : {}", line_count);
-    println!("first column count : {}", first_numbers.len());
-    println!("second column count: {}", second_numbers.len());
+/// The value after `flag`, unless it is missing or is itself another flag.
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+        .filter(|value| !value.starts_with("--"))
+}
 
-    if first_numbers.len() != second_numbers.len() {
-        return Err(format!(
-            "expecped to match number count in colums: first={}, second={}",
-            first_numbers.len(),
-            second_numbers.len()
-        ));
-    }
+fn parse_value<T: FromStr>(flag: &str, value: &str, expects: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects {}, got `{}`", flag, expects, value))
+}
 
-    Ok((first_numbers, second_numbers))
+fn parse_columns(value: &str) -> Result<(usize, usize), String> {
+    let (a, b) = value.split_once(',').ok_or_else(|| {
+        format!(
+            "--columns expects two column indexes `a,b`, got `{}`",
+            value
+        )
+    })?;
+    Ok((
+        parse_value("--columns", a, "a column index")?,
+        parse_value("--columns", b, "a column index")?,
+    ))
 }
 
-fn create_sorted_tuples(mut first: Vec<i64>, mut second: Vec<i64>) -> Vec<(i64, i64)> {
-    first.sort_unstable();
-    second.sort_unstable();
-    first.into_iter().zip(second.into_iter()).collect()
+/// The parsed value of `flag`, or `None` if the flag is not given. A flag
+/// given without a value, or with a malformed one, is an error.
+fn flag_value<T: FromStr>(args: &[String], flag: &str, expects: &str) -> Result<Option<T>, String> {
+    if !args.iter().any(|a| a == flag) {
+        return Ok(None);
+    }
+    let value = arg_value(args, flag).ok_or_else(|| format!("{} expects {}", flag, expects))?;
+    parse_value(flag, value, expects).map(Some)
 }
 
-fn calculate_abs_diff((a, b): &(i64, i64)) -> i64 {
-    if a > b {
-        // println!("{} - {} = {}", a, b, a - b);
-        a - b
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let mode = if args.iter().any(|a| a == "--lenient") {
+        Ingestion::Lenient
     } else {
-        // println!("{} - {} = {}", b, a, b - a);
-        b - a
+        Ingestion::Strict
+    };
+
+    if args.iter().any(|a| a == "--stdin") {
+        let every = match flag_value(&args, "--every", "a row count") {
+            Ok(every) => every.unwrap_or(1000),
+            Err(e) => return eprintln!("error occurred: {}", e),
+        };
+        let result = stream_totals(io::stdin().lock(), mode, every, |totals| {
+            eprintln!(
+                "rows: {:>10}  similarity score: {}",
+                totals.count, totals.similarity_score
+            )
+        });
+        let (totals, rejected) = match result {
+            Ok(result) => result,
+            Err(e) => return eprintln!("error occurred: {}", e),
+        };
+        warn_rejected(&rejected);
+        println!("\nrows            : {}", totals.count);
+        println!("similarity score: {}", totals.similarity_score);
+        match totals.total_distance() {
+            Ok(total) => println!("total distance  : {}\n\n", total),
+            Err(e) => eprintln!("error occurred: {}", e),
+        }
+        return;
     }
-}
 
-fn sum_differences(tuples: &[(i64, i64)]) -> i64 {
-    // println!("\ncalculating the diff:");
-    let sum = tuples.iter().map(calculate_abs_diff).sum();
-    // println!("\sum of differences: {}", sum);
-    sum
-}
+    if args.iter().any(|a| a == "--external") {
+        // The run length is optional: `--external` alone uses the default.
+        let run_len = match arg_value(&args, "--external")
+            .map(|v| parse_value("--external", v, "a run length"))
+        {
+            Some(Ok(run_len)) => run_len,
+            Some(Err(e)) => return eprintln!("error occurred: {}", e),
+            None => DEFAULT_RUN_LEN,
+        };
+        let result = File::open("input")
+            .map_err(Error::Open)
+            .and_then(|file| external_sum_differences(BufReader::new(file), run_len, mode));
+        match result {
+            Ok((total, rejected)) => {
+                warn_rejected(&rejected);
+                println!("\ntotal distance  : {}\n\n", total)
+            }
+            Err(e) => eprintln!("error occurred: {}", e),
+        }
+        return;
+    }
 
-fn process_file<P: AsRef<Path>>(path: P) -> Result<i64, String> {
-    let (first_numbers, second_numbers) = read_numbers_from_file(path)?;
-    let tuples = create_sorted_tuples(first_numbers, second_numbers);
-    Ok(sum_differences(&tuples))
-}
+    if args.iter().any(|a| a == "--report") {
+        let format = match arg_value(&args, "--report").map(str::parse::<ReportFormat>) {
+            Some(Ok(f)) => f,
+            Some(Err(e)) => return eprintln!("error occurred: {}", e),
+            None => ReportFormat::Csv,
+        };
+        let out_path = arg_value(&args, "--out");
+        let ingested = match File::open("input")
+            .map_err(Error::Open)
+            .and_then(|file| read_numbers(BufReader::new(file), mode))
+        {
+            Ok(ingested) => ingested,
+            Err(e) => return eprintln!("error occurred: {}", e),
+        };
+        warn_rejected(&ingested.rejected);
+        let report = match Report::new(&create_sorted_tuples(ingested.first, ingested.second)) {
+            Ok(report) => report,
+            Err(e) => return eprintln!("error occurred: {}", e),
+        };
+        let out: Box<dyn Write> = match out_path {
+            Some(path) => match File::create(path) {
+                Ok(file) => Box::new(io::BufWriter::new(file)),
+                Err(e) => return eprintln!("error occurred: cannot create {}: {}", path, e),
+            },
+            None => Box::new(io::stdout().lock()),
+        };
+        if let Err(e) = report.write(format, out) {
+            eprintln!("error occurred: failed to write report: {}", e);
+        }
+        return;
+    }
 
-fn main() {
-    match process_file("input") {
-        Ok(result) => println!("\ntotal distance: {}\n\n", result),
+    if args.iter().any(|a| a == "--diff") {
+        let ingested = match File::open("input")
+            .map_err(Error::Open)
+            .and_then(|file| read_numbers(BufReader::new(file), mode))
+        {
+            Ok(ingested) => ingested,
+            Err(e) => return eprintln!("error occurred: {}", e),
+        };
+        warn_rejected(&ingested.rejected);
+        let diff = MultisetDiff::new(&ingested.first, &ingested.second);
+        let result = match arg_value(&args, "--diff") {
+            Some("json") => diff.write_json(io::stdout().lock()),
+            Some("text") | None => diff.write_text(io::stdout().lock()),
+            Some(other) => return eprintln!("error occurred: unknown diff format: {}", other),
+        };
+        if let Err(e) = result {
+            eprintln!("error occurred: failed to write diff: {}", e);
+        }
+        return;
+    }
+
+    if args.iter().any(|a| a == "--stats") {
+        let percentiles: Vec<f64> = match arg_value(&args, "--stats") {
+            Some(list) => match list.split(',').map(str::parse).collect() {
+                Ok(p) => p,
+                Err(_) => return eprintln!("error occurred: invalid percentile list: {}", list),
+            },
+            None => vec![90.0, 95.0, 99.0],
+        };
+        let buckets = match flag_value(&args, "--buckets", "a bucket count") {
+            Ok(buckets) => buckets.unwrap_or(10),
+            Err(e) => return eprintln!("error occurred: {}", e),
+        };
+        let tuples = match read_numbers_from_file("input", mode) {
+            Ok(ingested) => {
+                warn_rejected(&ingested.rejected);
+                create_sorted_tuples(ingested.first, ingested.second)
+            }
+            Err(e) => return eprintln!("error occurred: {}", e),
+        };
+        let Some(stats) = DistanceStats::new(&tuples, &percentiles) else {
+            return println!("\nno pairs\n");
+        };
+
+        println!("\npairs   : {}", stats.count);
+        println!("min     : {}", stats.min);
+        println!("max     : {}", stats.max);
+        println!("mean    : {:.3}", stats.mean);
+        println!("median  : {:.3}", stats.median);
+        println!("std dev : {:.3}", stats.std_dev);
+        for (p, value) in &stats.percentiles {
+            println!("p{:<7}: {:.3}", p, value);
+        }
+        println!("\n{}", histogram(&sorted_distances(&tuples), buckets, 40));
+        return;
+    }
+
+    if args.iter().any(|a| a == "--table") {
+        let delimiter = match arg_value(&args, "--table").map(str::parse::<Delimiter>) {
+            Some(Ok(d)) => d,
+            Some(Err(e)) => return eprintln!("error occurred: {}", e),
+            None => Delimiter::Whitespace,
+        };
+        let columns = match flag_value::<String>(&args, "--columns", "two column indexes `a,b`")
+            .and_then(|value| value.map_or(Ok((0, 1)), |v| parse_columns(&v)))
+        {
+            Ok(columns) => columns,
+            Err(e) => return eprintln!("error occurred: {}", e),
+        };
+        let table = match File::open("input")
+            .map_err(Error::Open)
+            .and_then(|file| Table::read(BufReader::new(file), delimiter, mode))
+        {
+            Ok(table) => table,
+            Err(e) => return eprintln!("error occurred: {}", e),
+        };
+        warn_rejected(&table.rejected);
+
+        if args.iter().any(|a| a == "--matrix") {
+            let matrix = match table.distance_matrix() {
+                Ok(matrix) => matrix,
+                Err(e) => return eprintln!("error occurred: {}", e),
+            };
+            for row in matrix {
+                let row: Vec<String> = row.iter().map(|d| format!("{:>12}", d)).collect();
+                println!("{}", row.join(""));
+            }
+        } else {
+            let (a, b) = columns;
+            match table.column_distance(a, b) {
+                Ok(total) => println!("\ntotal distance  : {}\n\n", total),
+                Err(e) => eprintln!("error occurred: {}", e),
+            }
+        }
+        return;
+    }
+
+    if args.iter().any(|a| a == "--assign") {
+        let metric = match arg_value(&args, "--assign").map(str::parse::<BuiltinMetric>) {
+            Some(Ok(m)) => m,
+            Some(Err(e)) => return eprintln!("error occurred: {}", e),
+            None => BuiltinMetric::Absolute,
+        };
+        let ingested = match read_numbers_from_file("input", mode) {
+            Ok(ingested) => ingested,
+            Err(e) => return eprintln!("error occurred: {}", e),
+        };
+        warn_rejected(&ingested.rejected);
+        match assign_with_metric(&ingested.first, &ingested.second, &metric) {
+            Ok(assignment) => println!("\noptimal {} cost: {}\n\n", metric, assignment.total_cost),
+            Err(e) => eprintln!("error occurred: {}", e),
+        }
+        return;
+    }
+
+    let mut metrics = Vec::new();
+    for pair in args.windows(2).filter(|w| w[0] == "--metric") {
+        match pair[1].parse::<BuiltinMetric>() {
+            Ok(m) => metrics.push(m),
+            Err(e) => return eprintln!("error occurred: {}", e),
+        }
+    }
+    let metrics: Vec<&dyn Metric> = metrics.iter().map(|m| m as &dyn Metric).collect();
+
+    match process_file("input", mode, &metrics) {
+        Ok((scores, ingested)) => {
+            if !ingested.rejected.is_empty() {
+                eprintln!("{}", ingested.summary());
+            }
+            warn_rejected(&ingested.rejected);
+            println!("\ntotal distance  : {}", scores.total_distance);
+            println!("similarity score: {}", scores.similarity_score);
+            for (name, total) in &scores.metric_totals {
+                println!("{:<16}: {}", name, total);
+            }
+            println!("\n");
+        }
         Err(e) => eprintln!("error occurred: {}", e),
     }
 }
@@ -0,0 +1,171 @@
+//! Pair-distance metrics for the sorted tuples.
+//!
+//! `calculate_abs_diff` remains the exact integer L1 distance; the metrics here
+//! work in `f64` so that squared and relative distances share one interface.
+
+use std::fmt;
+use std::str::FromStr;
+
+pub trait Metric {
+    fn name(&self) -> String;
+    fn distance(&self, a: i64, b: i64) -> f64;
+
+    /// Whether this is exactly `|a - b|`, for which pairing the sorted columns
+    /// is known to be optimal. Decided by the metric itself, not its name.
+    fn is_l1(&self) -> bool {
+        false
+    }
+}
+
+fn abs_diff(a: i64, b: i64) -> f64 {
+    a.abs_diff(b) as f64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BuiltinMetric {
+    /// `|a - b|`
+    Absolute,
+    /// `(a - b)^2`
+    Squared,
+    /// `|a - b|` as a percentage of the larger magnitude; zero when both are zero.
+    RelativePercent,
+    /// `|a - b|`, clamped to at most the given cap.
+    Capped(f64),
+    /// `|a - b|`, but differences below the threshold count as zero.
+    Thresholded(f64),
+}
+
+impl Metric for BuiltinMetric {
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn is_l1(&self) -> bool {
+        *self == BuiltinMetric::Absolute
+    }
+
+    fn distance(&self, a: i64, b: i64) -> f64 {
+        let d = abs_diff(a, b);
+        match *self {
+            BuiltinMetric::Absolute => d,
+            BuiltinMetric::Squared => d * d,
+            BuiltinMetric::RelativePercent => {
+                let scale = a.unsigned_abs().max(b.unsigned_abs()) as f64;
+                if scale == 0.0 {
+                    0.0
+                } else {
+                    d / scale * 100.0
+                }
+            }
+            BuiltinMetric::Capped(cap) => d.min(cap),
+            BuiltinMetric::Thresholded(threshold) => {
+                if d < threshold {
+                    0.0
+                } else {
+                    d
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for BuiltinMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuiltinMetric::Absolute => write!(f, "abs"),
+            BuiltinMetric::Squared => write!(f, "squared"),
+            BuiltinMetric::RelativePercent => write!(f, "relative"),
+            BuiltinMetric::Capped(cap) => write!(f, "capped:{}", cap),
+            BuiltinMetric::Thresholded(threshold) => write!(f, "threshold:{}", threshold),
+        }
+    }
+}
+
+/// Parses the names produced by `Display`, e.g. `abs`, `squared`, `relative`,
+/// `capped:100` or `threshold:5`.
+impl FromStr for BuiltinMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, arg) = match s.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (s, None),
+        };
+        let value = || {
+            arg.ok_or_else(|| format!("metric `{}` needs a value, e.g. `{}:10`", name, name))?
+                .parse::<f64>()
+                .map_err(|_| format!("invalid value for metric `{}`: {}", name, s))
+        };
+        match name {
+            "abs" => Ok(BuiltinMetric::Absolute),
+            "squared" => Ok(BuiltinMetric::Squared),
+            "relative" => Ok(BuiltinMetric::RelativePercent),
+            "capped" => Ok(BuiltinMetric::Capped(value()?)),
+            "threshold" => Ok(BuiltinMetric::Thresholded(value()?)),
+            _ => Err(format!("unknown metric: {}", s)),
+        }
+    }
+}
+
+/// A user-supplied metric built from a closure.
+pub struct FnMetric<F> {
+    name: String,
+    f: F,
+}
+
+impl<F: Fn(i64, i64) -> f64> FnMetric<F> {
+    pub fn new(name: &str, f: F) -> Self {
+        FnMetric {
+            name: name.to_string(),
+            f,
+        }
+    }
+}
+
+impl<F: Fn(i64, i64) -> f64> Metric for FnMetric<F> {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn distance(&self, a: i64, b: i64) -> f64 {
+        (self.f)(a, b)
+    }
+}
+
+/// Sums every metric over the tuples in a single pass, in the order given.
+pub fn sum_metrics(tuples: &[(i64, i64)], metrics: &[&dyn Metric]) -> Vec<f64> {
+    let mut sums = vec![0.0; metrics.len()];
+    for &(a, b) in tuples {
+        for (sum, metric) in sums.iter_mut().zip(metrics) {
+            *sum += metric.distance(a, b);
+        }
+    }
+    sums
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_metrics() {
+        let tuples = [(1, 3), (2, 3), (3, 3), (3, 4), (3, 5), (4, 9)];
+        let metrics: Vec<BuiltinMetric> = ["abs", "squared", "capped:2", "threshold:2"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let refs: Vec<&dyn Metric> = metrics.iter().map(|m| m as &dyn Metric).collect();
+        assert_eq!(sum_metrics(&tuples, &refs), vec![11.0, 35.0, 8.0, 9.0]);
+
+        assert_eq!(BuiltinMetric::RelativePercent.distance(50, 100), 50.0);
+        assert_eq!(BuiltinMetric::RelativePercent.distance(0, 0), 0.0);
+        assert!("capped".parse::<BuiltinMetric>().is_err());
+    }
+
+    #[test]
+    fn test_custom_metric() {
+        let over = FnMetric::new("over", |a, b| (a - b).max(0) as f64);
+        assert_eq!(over.name(), "over");
+        assert_eq!(sum_metrics(&[(5, 2), (1, 4)], &[&over]), vec![3.0]);
+    }
+}
@@ -0,0 +1,155 @@
+//! Per-pair report of the sorted tuples, exportable as CSV or JSON Lines.
+
+use crate::error::Error;
+use crate::{add_distance, calculate_abs_diff};
+use std::io::{self, Write};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Csv,
+    JsonLines,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(ReportFormat::Csv),
+            "jsonl" | "json-lines" => Ok(ReportFormat::JsonLines),
+            _ => Err(format!("unknown report format: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PairRow {
+    /// 1-based position of the pair in sorted order.
+    pub rank: usize,
+    pub left: i64,
+    pub right: i64,
+    pub distance: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub pairs: usize,
+    pub total_distance: u64,
+    pub min_distance: Option<u64>,
+    pub max_distance: Option<u64>,
+    pub mean_distance: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub rows: Vec<PairRow>,
+    pub summary: Summary,
+}
+
+fn json_opt<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "null".to_string(), |v| v.to_string())
+}
+
+impl Report {
+    pub fn new(tuples: &[(i64, i64)]) -> Result<Report, Error> {
+        let rows: Vec<PairRow> = tuples
+            .iter()
+            .enumerate()
+            .map(|(i, pair)| PairRow {
+                rank: i + 1,
+                left: pair.0,
+                right: pair.1,
+                distance: calculate_abs_diff(pair),
+            })
+            .collect();
+
+        let total_distance = rows.iter().map(|r| r.distance).try_fold(0, add_distance)?;
+        let summary = Summary {
+            pairs: rows.len(),
+            total_distance,
+            min_distance: rows.iter().map(|r| r.distance).min(),
+            max_distance: rows.iter().map(|r| r.distance).max(),
+            mean_distance: (!rows.is_empty()).then(|| total_distance as f64 / rows.len() as f64),
+        };
+        Ok(Report { rows, summary })
+    }
+
+    pub fn write<W: Write>(&self, format: ReportFormat, out: W) -> io::Result<()> {
+        match format {
+            ReportFormat::Csv => self.write_csv(out),
+            ReportFormat::JsonLines => self.write_jsonl(out),
+        }
+    }
+
+    /// One `rank,left,right,distance` row per pair. The summary follows as `#`
+    /// comment lines so CSV readers that honour comments skip it.
+    pub fn write_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "rank,left,right,distance")?;
+        for r in &self.rows {
+            writeln!(out, "{},{},{},{}", r.rank, r.left, r.right, r.distance)?;
+        }
+        let s = &self.summary;
+        writeln!(out, "# pairs={}", s.pairs)?;
+        writeln!(out, "# total_distance={}", s.total_distance)?;
+        writeln!(out, "# min_distance={}", json_opt(s.min_distance))?;
+        writeln!(out, "# max_distance={}", json_opt(s.max_distance))?;
+        writeln!(out, "# mean_distance={}", json_opt(s.mean_distance))?;
+        out.flush()
+    }
+
+    /// One `{"type":"pair",...}` object per pair, then a single
+    /// `{"type":"summary",...}` object.
+    pub fn write_jsonl<W: Write>(&self, mut out: W) -> io::Result<()> {
+        for r in &self.rows {
+            writeln!(
+                out,
+                r#"{{"type":"pair","rank":{},"left":{},"right":{},"distance":{}}}"#,
+                r.rank, r.left, r.right, r.distance
+            )?;
+        }
+        let s = &self.summary;
+        writeln!(
+            out,
+            r#"{{"type":"summary","pairs":{},"total_distance":{},"min_distance":{},"max_distance":{},"mean_distance":{}}}"#,
+            s.pairs,
+            s.total_distance,
+            json_opt(s.min_distance),
+            json_opt(s.max_distance),
+            json_opt(s.mean_distance)
+        )?;
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_exports() {
+        let report = Report::new(&[(1, 3), (2, 3), (3, 3), (3, 4)]).unwrap();
+        assert_eq!(report.summary.total_distance, 4);
+        assert_eq!(report.summary.mean_distance, Some(1.0));
+
+        let mut csv = Vec::new();
+        report.write(ReportFormat::Csv, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("rank,left,right,distance\n1,1,3,2\n2,2,3,1\n"));
+        assert!(csv.contains("# max_distance=2\n"));
+
+        let mut jsonl = Vec::new();
+        report.write(ReportFormat::JsonLines, &mut jsonl).unwrap();
+        let jsonl = String::from_utf8(jsonl).unwrap();
+        let lines: Vec<&str> = jsonl.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(
+            lines[3],
+            r#"{"type":"pair","rank":4,"left":3,"right":4,"distance":1}"#
+        );
+        assert_eq!(
+            lines[4],
+            r#"{"type":"summary","pairs":4,"total_distance":4,"min_distance":0,"max_distance":2,"mean_distance":1}"#
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Integer sorting fast paths for the location columns.
+//!
+//! Historian IDs are dense, mostly five-digit values, so when the observed
+//! min/max span is small a counting sort beats `sort_unstable` by a wide margin.
+//! Wider ranges use `sort_unstable` itself; an LSD radix sort was tried for
+//! them and lost to it on every input in `benches/sort.rs`.
+
+/// Below this many values the comparison sort is already fast enough.
+const SMALL_INPUT: usize = 256;
+/// Counting sort is used while the value range is at most this many times the
+/// input length (plus a fixed allowance), keeping the count table cache-sized.
+const COUNTING_RANGE_FACTOR: u64 = 2;
+const COUNTING_RANGE_SLACK: u64 = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortStrategy {
+    Comparison,
+    Counting,
+}
+
+fn min_max(values: &[i64]) -> Option<(i64, i64)> {
+    let first = *values.first()?;
+    Some(
+        values
+            .iter()
+            .fold((first, first), |(lo, hi), &v| (lo.min(v), hi.max(v))),
+    )
+}
+
+/// Whether a count table for `range` is small enough for `len` values.
+fn counting_fits(len: usize, range: u64) -> bool {
+    range <= len as u64 * COUNTING_RANGE_FACTOR + COUNTING_RANGE_SLACK
+}
+
+pub fn choose_strategy(values: &[i64]) -> SortStrategy {
+    if values.len() < SMALL_INPUT {
+        return SortStrategy::Comparison;
+    }
+    let Some((min, max)) = min_max(values) else {
+        return SortStrategy::Comparison;
+    };
+    if counting_fits(values.len(), max.abs_diff(min)) {
+        SortStrategy::Counting
+    } else {
+        SortStrategy::Comparison
+    }
+}
+
+/// Sorts `values` ascending with the strategy picked by `choose_strategy`, and
+/// returns that strategy.
+pub fn sort_column(values: &mut [i64]) -> SortStrategy {
+    let strategy = choose_strategy(values);
+    sort_with(values, strategy);
+    strategy
+}
+
+/// Sorts `values` ascending with the given strategy. `Counting` falls back to
+/// the comparison sort when the value range is too wide for a count table, so
+/// any input is safe to pass.
+pub fn sort_with(values: &mut [i64], strategy: SortStrategy) {
+    let Some((min, max)) = min_max(values) else {
+        return;
+    };
+    match strategy {
+        SortStrategy::Comparison => values.sort_unstable(),
+        SortStrategy::Counting if !counting_fits(values.len(), max.abs_diff(min)) => {
+            values.sort_unstable()
+        }
+        SortStrategy::Counting => counting_sort(values, min, max),
+    }
+}
+
+fn counting_sort(values: &mut [i64], min: i64, max: i64) {
+    let mut counts = vec![0usize; max.abs_diff(min) as usize + 1];
+    for &v in values.iter() {
+        counts[v.abs_diff(min) as usize] += 1;
+    }
+    let mut i = 0;
+    for (offset, &count) in counts.iter().enumerate() {
+        let v = min.wrapping_add(offset as i64);
+        values[i..i + count].fill(v);
+        i += count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn random(n: usize, modulus: u64, seed: u64) -> Vec<i64> {
+        let mut rng = Rng::new(seed);
+        (0..n)
+            .map(|_| (rng.below(modulus) as i64).wrapping_sub((modulus / 2) as i64))
+            .collect()
+    }
+
+    #[test]
+    fn test_strategies_match_sort_unstable() {
+        let mut extremes = random(1_000, u64::MAX, 7);
+        extremes.extend([i64::MIN, i64::MAX, 0, -1]);
+        let inputs = [
+            random(100, 1_000, 1),
+            random(5_000, 90_000, 2),
+            random(5_000, 1 << 40, 3),
+            extremes,
+            vec![i64::MIN, 0, i64::MAX],
+        ];
+        for values in inputs {
+            let mut expected = values.clone();
+            expected.sort_unstable();
+            let mut sorted = values.clone();
+            sort_with(&mut sorted, SortStrategy::Counting);
+            assert_eq!(sorted, expected);
+            let mut sorted = values.clone();
+            sort_column(&mut sorted);
+            assert_eq!(sorted, expected);
+        }
+    }
+
+    #[test]
+    fn test_choose_strategy() {
+        assert_eq!(
+            choose_strategy(&random(10, 100, 1)),
+            SortStrategy::Comparison
+        );
+        assert_eq!(
+            choose_strategy(&random(10_000, 50_000, 1)),
+            SortStrategy::Counting
+        );
+        assert_eq!(
+            choose_strategy(&random(10_000, 1 << 40, 1)),
+            SortStrategy::Comparison
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Distribution statistics over the per-pair distances, used to tell whether a
+//! large total comes from a few outliers or from a broad shift.
+
+use crate::calculate_abs_diff;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceStats {
+    pub count: usize,
+    pub min: u64,
+    pub max: u64,
+    pub mean: f64,
+    pub median: f64,
+    /// Population standard deviation.
+    pub std_dev: f64,
+    /// `(p, value)` for each requested percentile `p` in `0..=100`.
+    pub percentiles: Vec<(f64, f64)>,
+}
+
+/// Percentile of already sorted values, interpolating linearly between the two
+/// closest ranks.
+fn percentile(sorted: &[u64], p: f64) -> f64 {
+    let rank = (p.clamp(0.0, 100.0) / 100.0) * (sorted.len() - 1) as f64;
+    let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+    let frac = rank - lo as f64;
+    sorted[lo] as f64 + (sorted[hi] - sorted[lo]) as f64 * frac
+}
+
+pub fn sorted_distances(tuples: &[(i64, i64)]) -> Vec<u64> {
+    let mut distances: Vec<u64> = tuples.iter().map(calculate_abs_diff).collect();
+    distances.sort_unstable();
+    distances
+}
+
+impl DistanceStats {
+    /// Returns `None` when there are no pairs.
+    pub fn new(tuples: &[(i64, i64)], percentiles: &[f64]) -> Option<DistanceStats> {
+        let distances = sorted_distances(tuples);
+        let (&min, &max) = (distances.first()?, distances.last()?);
+        let n = distances.len() as f64;
+        let mean = distances.iter().map(|&d| d as f64).sum::<f64>() / n;
+        let variance = distances
+            .iter()
+            .map(|&d| (d as f64 - mean).powi(2))
+            .sum::<f64>()
+            / n;
+
+        Some(DistanceStats {
+            count: distances.len(),
+            min,
+            max,
+            mean,
+            median: percentile(&distances, 50.0),
+            std_dev: variance.sqrt(),
+            percentiles: percentiles
+                .iter()
+                .map(|&p| (p, percentile(&distances, p)))
+                .collect(),
+        })
+    }
+}
+
+/// Text histogram of `distances` over `buckets` equal-width buckets spanning
+/// `min..=max`, with bars scaled so the fullest bucket is `bar_width` wide.
+pub fn histogram(distances: &[u64], buckets: usize, bar_width: usize) -> String {
+    let (Some(&min), Some(&max)) = (distances.iter().min(), distances.iter().max()) else {
+        return String::new();
+    };
+    let buckets = buckets.max(1);
+    let span = (max - min) as f64 + 1.0;
+    let mut counts = vec![0usize; buckets];
+    for &d in distances {
+        let i = (((d - min) as f64 / span) * buckets as f64) as usize;
+        counts[i.min(buckets - 1)] += 1;
+    }
+
+    let fullest = counts.iter().copied().max().unwrap_or(0).max(1);
+    let mut out = String::new();
+    for (i, &count) in counts.iter().enumerate() {
+        let lo = min as f64 + span * i as f64 / buckets as f64;
+        let hi = min as f64 + span * (i + 1) as f64 / buckets as f64;
+        let bar = "#".repeat(count * bar_width / fullest);
+        let _ = writeln!(
+            out,
+            "[{:>10.0}, {:>10.0}) {:>8} {}",
+            lo.ceil(),
+            hi.ceil(),
+            count,
+            bar
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance_stats() {
+        // distances: 2, 1, 0, 1, 2, 5
+        let tuples = [(1, 3), (2, 3), (3, 3), (3, 4), (3, 5), (4, 9)];
+        let stats = DistanceStats::new(&tuples, &[0.0, 90.0, 100.0]).unwrap();
+        assert_eq!((stats.count, stats.min, stats.max), (6, 0, 5));
+        assert!((stats.mean - 11.0 / 6.0).abs() < 1e-12);
+        assert_eq!(stats.median, 1.5);
+        assert!((stats.std_dev - (89.0f64 / 36.0).sqrt()).abs() < 1e-12);
+        assert_eq!(
+            stats.percentiles,
+            vec![(0.0, 0.0), (90.0, 3.5), (100.0, 5.0)]
+        );
+        assert!(DistanceStats::new(&[], &[]).is_none());
+    }
+
+    #[test]
+    fn test_histogram() {
+        let text = histogram(&[0, 1, 1, 2, 5, 5], 3, 4);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("3 ####"));
+        assert!(lines[1].ends_with("1 #"));
+        assert!(lines[2].ends_with("2 ##"));
+    }
+}
@@ -0,0 +1,144 @@
+//! Running totals for rows arriving on a stream (e.g. stdin).
+//!
+//! Both columns are kept as value -> count maps, so the similarity score can be
+//! updated per row and the sorted distance can be computed at end of stream by
+//! walking the two maps in lockstep. Memory grows with the number of distinct
+//! IDs rather than the number of rows.
+
+use crate::error::{Error, ParseError};
+use crate::{add_distance, process_line, Ingestion};
+use std::collections::BTreeMap;
+use std::io::BufRead;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RunningTotals {
+    pub count: usize,
+    pub similarity_score: i64,
+    left: BTreeMap<i64, u64>,
+    right: BTreeMap<i64, u64>,
+}
+
+fn overflow() -> Error {
+    Error::Overflow("similarity score")
+}
+
+impl RunningTotals {
+    pub fn push(&mut self, left: i64, right: i64) -> Result<(), Error> {
+        // score = sum of x * L[x] * R[x]; adding `left` gains left * R[left],
+        // then adding `right` gains right * L[right] (including the new left).
+        let r = *self.right.get(&left).unwrap_or(&0);
+        let gain_left = left.checked_mul(r as i64).ok_or_else(overflow)?;
+        *self.left.entry(left).or_insert(0) += 1;
+
+        let l = *self.left.get(&right).unwrap_or(&0);
+        let gain_right = right.checked_mul(l as i64).ok_or_else(overflow)?;
+        *self.right.entry(right).or_insert(0) += 1;
+
+        self.similarity_score = self
+            .similarity_score
+            .checked_add(gain_left)
+            .and_then(|s| s.checked_add(gain_right))
+            .ok_or_else(overflow)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Total distance of the sorted pairing of everything pushed so far.
+    pub fn total_distance(&self) -> Result<u64, Error> {
+        let mut left = self.left.iter().map(|(&v, &n)| (v, n));
+        let mut right = self.right.iter().map(|(&v, &n)| (v, n));
+        let (mut l, mut r) = (left.next(), right.next());
+        let mut total = 0;
+
+        while let (Some((lv, ln)), Some((rv, rn))) = (l, r) {
+            let run = ln.min(rn);
+            let distance = lv
+                .abs_diff(rv)
+                .checked_mul(run)
+                .ok_or(Error::Overflow("total distance"))?;
+            total = add_distance(total, distance)?;
+            l = if ln == run {
+                left.next()
+            } else {
+                Some((lv, ln - run))
+            };
+            r = if rn == run {
+                right.next()
+            } else {
+                Some((rv, rn - run))
+            };
+        }
+        Ok(total)
+    }
+}
+
+/// Feeds every row of `reader` into a `RunningTotals`, calling `on_progress`
+/// after each `every` accepted rows. In lenient mode malformed rows are
+/// returned alongside the totals instead of ending the stream.
+pub fn stream_totals<R, F>(
+    reader: R,
+    mode: Ingestion,
+    every: usize,
+    mut on_progress: F,
+) -> Result<(RunningTotals, Vec<ParseError>), Error>
+where
+    R: BufRead,
+    F: FnMut(&RunningTotals),
+{
+    let mut totals = RunningTotals::default();
+    let mut rejected = Vec::new();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|source| Error::Read {
+            line: i + 1,
+            source,
+        })?;
+        match process_line(&line, i + 1) {
+            Ok((left, right)) => totals.push(left, right)?,
+            Err(e) if mode == Ingestion::Lenient => {
+                rejected.push(e);
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        }
+        if every > 0 && totals.count % every == 0 {
+            on_progress(&totals);
+        }
+    }
+
+    Ok((totals, rejected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{create_sorted_tuples, similarity_score, sum_differences};
+
+    #[test]
+    fn test_running_totals_match_batch() {
+        let input = "3   4\n4   3\n2   5\n1   3\n3   9\n3   3\n";
+        let mut seen = Vec::new();
+        let (totals, rejected) = stream_totals(input.as_bytes(), Ingestion::Strict, 2, |t| {
+            seen.push((t.count, t.similarity_score))
+        })
+        .unwrap();
+
+        assert!(rejected.is_empty());
+        assert_eq!(seen, vec![(2, 7), (4, 10), (6, 31)]);
+        assert_eq!(totals.similarity_score, 31);
+        assert_eq!(totals.total_distance().unwrap(), 11);
+
+        let first = [7, -2, 7, 0, 7, 5, 5];
+        let second = [5, 7, 7, -2, 1, 9, 7];
+        let mut totals = RunningTotals::default();
+        for (&a, &b) in first.iter().zip(&second) {
+            totals.push(a, b).unwrap();
+        }
+        let expected = sum_differences(&create_sorted_tuples(first.to_vec(), second.to_vec()));
+        assert_eq!(totals.total_distance().unwrap(), expected.unwrap());
+        assert_eq!(
+            totals.similarity_score,
+            similarity_score(&first, &second).unwrap()
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! N-column historian exports in whitespace, CSV or TSV layout.
+
+use crate::error::{Error, ParseError, ParseErrorKind};
+use crate::{create_sorted_tuples, sort, sum_differences, Ingestion};
+use std::io::BufRead;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Delimiter {
+    #[default]
+    Whitespace,
+    Comma,
+    Tab,
+}
+
+impl FromStr for Delimiter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "whitespace" | "ws" => Ok(Delimiter::Whitespace),
+            "csv" => Ok(Delimiter::Comma),
+            "tsv" => Ok(Delimiter::Tab),
+            _ => Err(format!("unknown table format: {}", s)),
+        }
+    }
+}
+
+/// Splits a line into fields with the byte offset of each. Delimited fields are
+/// trimmed, so `1, 2` reads the same as `1,2`.
+fn split_fields(line: &str, delimiter: Delimiter) -> Vec<(usize, &str)> {
+    let offset = |s: &str| s.as_ptr() as usize - line.as_ptr() as usize;
+    let sep = match delimiter {
+        Delimiter::Whitespace => {
+            return line.split_whitespace().map(|s| (offset(s), s)).collect();
+        }
+        Delimiter::Comma => ',',
+        Delimiter::Tab => '\t',
+    };
+    line.split(sep)
+        .map(|field| {
+            let trimmed = field.trim();
+            // An all-blank field still needs a position to point the caret at.
+            let at = if trimmed.is_empty() { field } else { trimmed };
+            (offset(at), trimmed)
+        })
+        .collect()
+}
+
+/// Parses one row, which must have `width` fields once the width is known.
+fn parse_row(
+    line: &str,
+    line_count: usize,
+    delimiter: Delimiter,
+    width: Option<usize>,
+) -> Result<Vec<i64>, ParseError> {
+    let fields = split_fields(line, delimiter);
+    let expected = width.unwrap_or(fields.len());
+    let error = |kind, column, text: &str| ParseError {
+        kind,
+        line: line_count,
+        column,
+        text: text.to_string(),
+        line_text: line.to_string(),
+    };
+
+    if fields.len() < expected || expected == 0 {
+        let kind = ParseErrorKind::TooFewColumns {
+            expected: expected.max(1),
+            found: fields.len(),
+        };
+        return Err(error(kind, line.len(), ""));
+    }
+    if fields.len() > expected {
+        let (column, text) = fields[expected];
+        let kind = ParseErrorKind::TooManyColumns {
+            expected,
+            found: fields.len(),
+        };
+        return Err(error(kind, column, text));
+    }
+
+    fields
+        .into_iter()
+        .map(|(column, text)| {
+            text.parse::<i64>()
+                .map_err(|_| error(ParseErrorKind::InvalidNumber, column, text))
+        })
+        .collect()
+}
+
+/// A table of numbers stored column by column. Every row has `width` fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub width: usize,
+    pub columns: Vec<Vec<i64>>,
+    /// Rows dropped under [`Ingestion::Lenient`]; always empty when strict.
+    pub rejected: Vec<ParseError>,
+}
+
+impl Table {
+    /// Reads a table whose width is fixed by its first accepted row; any later
+    /// row with a different number of fields is rejected, failing the read
+    /// unless `mode` is lenient.
+    pub fn read<R: BufRead>(
+        reader: R,
+        delimiter: Delimiter,
+        mode: Ingestion,
+    ) -> Result<Table, Error> {
+        let mut columns: Vec<Vec<i64>> = Vec::new();
+        let mut width = None;
+        let mut rejected = Vec::new();
+
+        for (i, line) in reader.lines().enumerate() {
+            let line_count = i + 1;
+            let line = line.map_err(|source| Error::Read {
+                line: line_count,
+                source,
+            })?;
+            match parse_row(&line, line_count, delimiter, width) {
+                Ok(row) => {
+                    width = Some(row.len());
+                    columns.resize_with(row.len(), Vec::new);
+                    for (values, n) in columns.iter_mut().zip(row) {
+                        values.push(n);
+                    }
+                }
+                Err(e) if mode == Ingestion::Lenient => rejected.push(e),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(Table {
+            width: width.unwrap_or(0),
+            columns,
+            rejected,
+        })
+    }
+
+    fn column(&self, index: usize) -> Result<&Vec<i64>, Error> {
+        self.columns.get(index).ok_or(Error::NoSuchColumn {
+            index,
+            width: self.width,
+        })
+    }
+
+    /// Total distance between two columns.
+    pub fn column_distance(&self, a: usize, b: usize) -> Result<u64, Error> {
+        let (first, second) = (self.column(a)?, self.column(b)?);
+        sum_differences(&create_sorted_tuples(first.clone(), second.clone()))
+    }
+
+    /// Total distance for every pair of columns. The matrix is symmetric with a
+    /// zero diagonal; each column is sorted only once.
+    pub fn distance_matrix(&self) -> Result<Vec<Vec<u64>>, Error> {
+        let sorted: Vec<Vec<i64>> = self
+            .columns
+            .iter()
+            .map(|c| {
+                let mut c = c.clone();
+                sort::sort_column(&mut c);
+                c
+            })
+            .collect();
+
+        let mut matrix = vec![vec![0; self.width]; self.width];
+        for a in 0..self.width {
+            for b in a + 1..self.width {
+                let tuples: Vec<(i64, i64)> = sorted[a]
+                    .iter()
+                    .copied()
+                    .zip(sorted[b].iter().copied())
+                    .collect();
+                let d = sum_differences(&tuples)?;
+                matrix[a][b] = d;
+                matrix[b][a] = d;
+            }
+        }
+        Ok(matrix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_csv_and_distances() {
+        let input = "3, 4, 10\n4,3,10\n2,5,10\n1,3,10\n3,9,10\n3,3,10\n";
+        let table = Table::read(input.as_bytes(), Delimiter::Comma, Ingestion::Strict).unwrap();
+        assert_eq!(table.width, 3);
+        assert_eq!(table.columns[2], vec![10; 6]);
+        assert_eq!(table.column_distance(0, 1).unwrap(), 11);
+        assert!(matches!(
+            table.column_distance(0, 3),
+            Err(Error::NoSuchColumn { index: 3, width: 3 })
+        ));
+        assert_eq!(
+            table.distance_matrix().unwrap(),
+            vec![vec![0, 11, 44], vec![11, 0, 33], vec![44, 33, 0]]
+        );
+    }
+
+    #[test]
+    fn test_row_length_is_validated() {
+        let err = Table::read(
+            "1\t2\t3\n4\t5\n".as_bytes(),
+            Delimiter::Tab,
+            Ingestion::Strict,
+        )
+        .unwrap_err();
+        match err {
+            Error::Parse(e) => {
+                assert_eq!(e.line, 2);
+                assert_eq!(
+                    e.kind,
+                    ParseErrorKind::TooFewColumns {
+                        expected: 3,
+                        found: 2
+                    }
+                );
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+
+        let err =
+            Table::read("1,,3\n".as_bytes(), Delimiter::Comma, Ingestion::Strict).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Parse(ParseError {
+                kind: ParseErrorKind::InvalidNumber,
+                column: 2,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_lenient_table() {
+        let input = "x,1\n4,5\n1,2,3\n6\n7,8\n";
+        let table = Table::read(input.as_bytes(), Delimiter::Comma, Ingestion::Lenient).unwrap();
+        assert_eq!(table.columns, vec![vec![4, 7], vec![5, 8]]);
+        let lines: Vec<usize> = table.rejected.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![1, 3, 4]);
+    }
+}
@@ -0,0 +1,69 @@
+//! Throughput of sequential vs multi-threaded report classification.
+//!
+//! Run with `cargo bench --bench classify`, optionally passing the number of
+//! reports to generate: `cargo bench --bench classify -- 5000000`. Defaults to
+//! 2M reports (roughly 40 MB).
+
+use day_2_red_nosed_reports::generator::Rng;
+use day_2_red_nosed_reports::parallel::{classify_parallel, classify_reader};
+use day_2_red_nosed_reports::policy::SafetyPolicy;
+use day_2_red_nosed_reports::Check;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::time::Instant;
+
+fn main() {
+    let reports: u64 = std::env::args()
+        .skip(1)
+        .find_map(|a| a.parse().ok())
+        .unwrap_or(2_000_000);
+
+    let path = std::env::temp_dir().join(format!("classify-bench-{}.txt", std::process::id()));
+    let mut out = BufWriter::new(File::create(&path).unwrap());
+    let mut rng = Rng::new(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..reports {
+        let mut level = 50i64;
+        let len = 5 + rng.below(4);
+        for i in 0..len {
+            level += rng.below(7) as i64 - 2;
+            let sep = if i + 1 == len { '\n' } else { ' ' };
+            write!(out, "{}{}", level, sep).unwrap();
+        }
+    }
+    out.flush().unwrap();
+    drop(out);
+    let bytes = fs::metadata(&path).unwrap().len() as f64;
+
+    let policy = SafetyPolicy::default();
+    let start = Instant::now();
+    let file = BufReader::new(File::open(&path).unwrap());
+    let sequential = classify_reader(file, &policy, Check::Dampened).unwrap();
+    let baseline = start.elapsed();
+    println!(
+        "{} reports, {:.1} MB, {} available threads",
+        reports,
+        bytes / 1e6,
+        std::thread::available_parallelism().map_or(1, |n| n.get())
+    );
+    println!(
+        "sequential   {:>9.1?}  {:>7.1} MB/s",
+        baseline,
+        bytes / 1e6 / baseline.as_secs_f64()
+    );
+
+    for workers in [1, 2, 4, 8] {
+        let start = Instant::now();
+        let parallel = classify_parallel(&path, &policy, Check::Dampened, workers).unwrap();
+        let elapsed = start.elapsed();
+        assert_eq!(parallel, sequential, "{} workers disagree", workers);
+        println!(
+            "{} workers    {:>9.1?}  {:>7.1} MB/s  ({:.2}x)",
+            workers,
+            elapsed,
+            bytes / 1e6 / elapsed.as_secs_f64(),
+            baseline.as_secs_f64() / elapsed.as_secs_f64()
+        );
+    }
+
+    fs::remove_file(&path).unwrap();
+}
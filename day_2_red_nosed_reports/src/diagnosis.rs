@@ -0,0 +1,158 @@
+//! Explains why a report is unsafe: the first step that breaks a rule.
+
+use crate::policy::{Direction, SafetyPolicy};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// Fewer than two levels; there is no step to judge.
+    TooFewLevels,
+    /// The step goes against the direction set by the policy or by the first
+    /// non-zero step of the report.
+    DirectionFlip,
+    /// Two equal adjacent levels where the policy does not allow them.
+    ZeroStep,
+    StepTooSmall,
+    StepTooLarge,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Rule::TooFewLevels => "too few levels",
+            Rule::DirectionFlip => "direction flip",
+            Rule::ZeroStep => "zero step",
+            Rule::StepTooSmall => "step too small",
+            Rule::StepTooLarge => "step too large",
+        };
+        f.write_str(text)
+    }
+}
+
+/// The first bad step of a report. `index` is the position of the second level
+/// of the step, so the levels involved are `readings[index - 1]` and
+/// `readings[index]`. For `TooFewLevels` the index, levels and diff are zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Violation {
+    pub index: usize,
+    pub prev: i32,
+    pub next: i32,
+    pub diff: i64,
+    pub rule: Rule,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.rule == Rule::TooFewLevels {
+            return write!(f, "{}", self.rule);
+        }
+        write!(
+            f,
+            "{} at index {}: {} -> {} (diff {})",
+            self.rule, self.index, self.prev, self.next, self.diff
+        )
+    }
+}
+
+/// Returns `Ok(increasing)` for a safe report, where `increasing` is the
+/// direction it follows, or the first violation. With `Direction::Either` the
+/// direction is fixed by the first non-zero step, as the original check did.
+pub fn diagnose(readings: &[i32], policy: &SafetyPolicy) -> Result<bool, Violation> {
+    if readings.len() < 2 {
+        return Err(Violation {
+            index: 0,
+            prev: 0,
+            next: 0,
+            diff: 0,
+            rule: Rule::TooFewLevels,
+        });
+    }
+
+    let mut increasing = match policy.direction {
+        Direction::Increasing => Some(true),
+        Direction::Decreasing => Some(false),
+        Direction::Either => None,
+    };
+
+    for (i, pair) in readings.windows(2).enumerate() {
+        let (prev, next) = (pair[0], pair[1]);
+        let diff = next as i64 - prev as i64;
+        let violation = |rule| Violation {
+            index: i + 1,
+            prev,
+            next,
+            diff,
+            rule,
+        };
+
+        if diff == 0 {
+            if policy.allow_equal {
+                continue;
+            }
+            return Err(violation(Rule::ZeroStep));
+        }
+        if *increasing.get_or_insert(diff > 0) != (diff > 0) {
+            return Err(violation(Rule::DirectionFlip));
+        }
+        if diff.abs() > policy.max_step as i64 {
+            return Err(violation(Rule::StepTooLarge));
+        }
+        if diff.abs() < policy.min_step as i64 {
+            return Err(violation(Rule::StepTooSmall));
+        }
+    }
+
+    Ok(increasing.unwrap_or(true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::Rng;
+    use crate::{levels_safe, parse_levels};
+
+    fn rule_of(line: &str) -> Option<(usize, Rule)> {
+        let readings = parse_levels(line).unwrap();
+        diagnose(&readings, &SafetyPolicy::default())
+            .err()
+            .map(|v| (v.index, v.rule))
+    }
+
+    #[test]
+    fn test_diagnose() {
+        assert_eq!(rule_of("7 6 4 2 1"), None);
+        assert_eq!(rule_of("1 2 7 8 9"), Some((2, Rule::StepTooLarge)));
+        assert_eq!(rule_of("1 3 2 4 5"), Some((2, Rule::DirectionFlip)));
+        assert_eq!(rule_of("8 6 4 4 1"), Some((3, Rule::ZeroStep)));
+        assert_eq!(rule_of("5"), Some((0, Rule::TooFewLevels)));
+
+        let v = diagnose(&[9, 7, 6, 2, 1], &SafetyPolicy::default()).unwrap_err();
+        assert_eq!(v.to_string(), "step too large at index 3: 6 -> 2 (diff -4)");
+
+        let strict_up = SafetyPolicy {
+            min_step: 2,
+            direction: Direction::Increasing,
+            ..SafetyPolicy::default()
+        };
+        let v = diagnose(&[5, 4], &strict_up).unwrap_err();
+        assert_eq!(v.rule, Rule::DirectionFlip);
+        let v = diagnose(&[1, 3, 4], &strict_up).unwrap_err();
+        assert_eq!((v.index, v.rule), (2, Rule::StepTooSmall));
+    }
+
+    #[test]
+    fn test_diagnose_agrees_with_levels_safe() {
+        let policy = SafetyPolicy {
+            allow_equal: true,
+            ..SafetyPolicy::default()
+        };
+        let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+        let mut next = |bound: u64| rng.below(bound);
+        for _ in 0..5_000 {
+            let readings: Vec<i32> = (0..1 + next(7)).map(|_| next(8) as i32).collect();
+            for p in [SafetyPolicy::default(), policy] {
+                assert_eq!(diagnose(&readings, &p).is_ok(), levels_safe(&readings, &p));
+            }
+        }
+    }
+}
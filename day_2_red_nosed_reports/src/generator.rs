@@ -0,0 +1,290 @@
+//! Synthetic reports with known verdicts, for stress and property testing.
+//!
+//! Safe reports are random walks whose every step satisfies the policy. Unsafe
+//! reports start out the same way and then get exactly one injected fault,
+//! built so that it always breaks the policy. The plain verdict is therefore
+//! known by construction; tolerance modes can be checked against a brute-force
+//! oracle on the same reports.
+
+use crate::policy::SafetyPolicy;
+
+/// Small xorshift generator so reports are reproducible from a seed.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Uniform-ish in `0..bound`.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// Uniform in `lo..=hi`.
+    pub fn range(&mut self, lo: i64, hi: i64) -> i64 {
+        lo + (self.next_u64() % (hi - lo + 1) as u64) as i64
+    }
+
+    pub fn chance(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthDistribution {
+    Fixed(usize),
+    /// Uniform in `min..=max`.
+    Uniform {
+        min: usize,
+        max: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// One level spikes past the allowed step and the next level returns.
+    BadLevel,
+    /// One step goes against the report's direction.
+    DirectionFlip,
+    /// One step is larger than `max_step`; later levels carry on from there.
+    OversizedJump,
+}
+
+pub const ALL_FAULTS: [Fault; 3] = [Fault::BadLevel, Fault::DirectionFlip, Fault::OversizedJump];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratorConfig {
+    pub policy: SafetyPolicy,
+    pub lengths: LengthDistribution,
+    /// Fraction of reports generated safe, in `0.0..=1.0`.
+    pub safe_fraction: f64,
+    /// Faults to pick from, uniformly, for unsafe reports.
+    pub faults: Vec<Fault>,
+    pub seed: u64,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig {
+            policy: SafetyPolicy::default(),
+            lengths: LengthDistribution::Uniform { min: 5, max: 8 },
+            safe_fraction: 0.5,
+            faults: ALL_FAULTS.to_vec(),
+            seed: 0x9e37_79b9_7f4a_7c15,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedReport {
+    pub levels: Vec<i32>,
+    /// Verdict of the plain (no tolerance) check under the configured policy.
+    pub expected_safe: bool,
+    /// The injected fault and the index of the level it affects.
+    pub fault: Option<(Fault, usize)>,
+}
+
+impl GeneratedReport {
+    pub fn line(&self) -> String {
+        let levels: Vec<String> = self.levels.iter().map(i32::to_string).collect();
+        levels.join(" ")
+    }
+}
+
+/// Endless iterator of generated reports.
+pub struct Generator {
+    config: GeneratorConfig,
+    rng: Rng,
+}
+
+impl Generator {
+    pub fn new(config: GeneratorConfig) -> Self {
+        let rng = Rng::new(config.seed);
+        Generator { config, rng }
+    }
+
+    fn length(&mut self, at_least: usize) -> usize {
+        let len = match self.config.lengths {
+            LengthDistribution::Fixed(n) => n,
+            LengthDistribution::Uniform { min, max } => {
+                self.rng.range(min as i64, max.max(min) as i64) as usize
+            }
+        };
+        len.max(at_least)
+    }
+
+    /// A valid step in the given direction (`sign` is +1 or -1).
+    fn step(&mut self, sign: i64) -> i64 {
+        let p = &self.config.policy;
+        if p.allow_equal && self.rng.chance(0.2) {
+            return 0;
+        }
+        sign * self.rng.range(p.min_step as i64, p.max_step as i64)
+    }
+
+    fn walk(&mut self, len: usize, sign: i64) -> Vec<i64> {
+        let mut level = self.rng.range(20, 80);
+        let mut levels = vec![level];
+        for _ in 1..len {
+            level += self.step(sign);
+            levels.push(level);
+        }
+        levels
+    }
+
+    fn inject(&mut self, levels: &mut [i64], sign: i64, fault: Fault) -> usize {
+        let (min, max) = (
+            self.config.policy.min_step as i64,
+            self.config.policy.max_step as i64,
+        );
+        let n = levels.len();
+        match fault {
+            Fault::BadLevel => {
+                let p = self.rng.range(0, n as i64 - 1) as usize;
+                // Pushed away from its neighbour(s) by more than `max_step`; at
+                // the start it is pushed backwards so the first step overshoots.
+                let spike = sign * self.rng.range(max + 1, max + 5);
+                levels[p] += if p == 0 { -spike } else { spike };
+                p
+            }
+            Fault::DirectionFlip => {
+                let p = self.rng.range(1, n as i64 - 1) as usize;
+                // With equal steps allowed, a walk of zero steps has no
+                // direction to contradict, so the flip's neighbour must move.
+                let neighbour = if p >= 2 { p - 1 } else { p + 1 };
+                if levels[neighbour] == levels[neighbour - 1] {
+                    let step = sign * self.rng.range(min, max);
+                    levels[neighbour..].iter_mut().for_each(|l| *l += step);
+                }
+                let back = -sign * self.rng.range(1, max);
+                let shift = levels[p - 1] + back - levels[p];
+                levels[p..].iter_mut().for_each(|l| *l += shift);
+                p
+            }
+            Fault::OversizedJump => {
+                let p = self.rng.range(1, n as i64 - 1) as usize;
+                let jump = sign * self.rng.range(max + 1, max + 5);
+                let shift = levels[p - 1] + jump - levels[p];
+                levels[p..].iter_mut().for_each(|l| *l += shift);
+                p
+            }
+        }
+    }
+}
+
+impl Iterator for Generator {
+    type Item = GeneratedReport;
+
+    fn next(&mut self) -> Option<GeneratedReport> {
+        let directions = self.config.policy.directions();
+        let increasing = directions[self.rng.range(0, directions.len() as i64 - 1) as usize];
+        let sign = if increasing { 1 } else { -1 };
+        let safe = self.config.faults.is_empty() || self.rng.chance(self.config.safe_fraction);
+
+        // A flipped step needs a step on either side to contradict, so unsafe
+        // reports are at least three levels long.
+        let len = self.length(if safe { 2 } else { 3 });
+        let mut levels = self.walk(len, sign);
+        let fault = (!safe).then(|| {
+            let faults = &self.config.faults;
+            let fault = faults[self.rng.range(0, faults.len() as i64 - 1) as usize];
+            (fault, self.inject(&mut levels, sign, fault))
+        });
+
+        Some(GeneratedReport {
+            levels: levels.into_iter().map(|l| l as i32).collect(),
+            expected_safe: safe,
+            fault,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Direction;
+    use crate::testutil::subset_min_removals;
+    use crate::tolerance::min_removals;
+    use crate::{levels_safe, levels_safe_dampened};
+
+    #[test]
+    fn test_generator_properties() {
+        let configs = [
+            GeneratorConfig::default(),
+            GeneratorConfig {
+                policy: SafetyPolicy {
+                    min_step: 2,
+                    max_step: 5,
+                    direction: Direction::Decreasing,
+                    allow_equal: true,
+                },
+                lengths: LengthDistribution::Uniform { min: 2, max: 10 },
+                safe_fraction: 0.3,
+                seed: 42,
+                ..GeneratorConfig::default()
+            },
+            // Mostly-flat walks in either direction: a flip needs another
+            // non-zero step to contradict.
+            GeneratorConfig {
+                policy: SafetyPolicy {
+                    allow_equal: true,
+                    ..SafetyPolicy::default()
+                },
+                lengths: LengthDistribution::Uniform { min: 3, max: 5 },
+                seed: 7,
+                ..GeneratorConfig::default()
+            },
+        ];
+
+        for config in configs {
+            let policy = config.policy;
+            let safe_fraction = config.safe_fraction;
+            let reports: Vec<GeneratedReport> = Generator::new(config).take(4_000).collect();
+
+            let safe = reports.iter().filter(|r| r.expected_safe).count();
+            let observed = safe as f64 / reports.len() as f64;
+            assert!((observed - safe_fraction).abs() < 0.05, "{}", observed);
+
+            for report in &reports {
+                let levels = &report.levels;
+                assert_eq!(
+                    levels_safe(levels, &policy),
+                    report.expected_safe,
+                    "{:?}",
+                    report
+                );
+
+                let oracle = subset_min_removals(levels, &policy);
+                assert_eq!(
+                    levels_safe_dampened(levels, &policy),
+                    oracle.is_some_and(|k| k <= 1)
+                );
+                assert_eq!(min_removals(levels, &policy).map(|r| r.count()), oracle);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lengths_and_faults() {
+        let config = GeneratorConfig {
+            lengths: LengthDistribution::Fixed(6),
+            safe_fraction: 0.0,
+            faults: vec![Fault::OversizedJump],
+            ..GeneratorConfig::default()
+        };
+        for report in Generator::new(config).take(100) {
+            assert_eq!(report.levels.len(), 6);
+            assert!(!report.expected_safe);
+            assert!(matches!(report.fault, Some((Fault::OversizedJump, p)) if p >= 1));
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! One JSON object per report, for dashboards that ingest JSON Lines.
+
+use crate::diagnosis::diagnose;
+use crate::policy::SafetyPolicy;
+use crate::{parse_levels, Verdict};
+
+/// Direction of the first non-zero step, `flat` if every step is zero, or
+/// `None` for reports with fewer than two levels.
+pub fn observed_direction(readings: &[i32]) -> Option<&'static str> {
+    if readings.len() < 2 {
+        return None;
+    }
+    let direction = readings
+        .windows(2)
+        .map(|w| w[1].cmp(&w[0]))
+        .find(|o| o.is_ne())
+        .map_or("flat", |o| {
+            if o.is_gt() {
+                "increasing"
+            } else {
+                "decreasing"
+            }
+        });
+    Some(direction)
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Renders a classified report. Unsafe reports carry the index and rule of the
+/// first bad step; invalid ones carry the token that failed to parse.
+pub fn report_json(
+    line_num: usize,
+    line: &str,
+    verdict: &Verdict,
+    policy: &SafetyPolicy,
+) -> String {
+    if let Verdict::Invalid(e) = verdict {
+        return format!(
+            r#"{{"line":{},"levels":null,"direction":null,"verdict":"invalid","error":{{"column":{},"token":{}}}}}"#,
+            line_num,
+            e.column,
+            json_string(&e.token)
+        );
+    }
+
+    let readings = parse_levels(line).unwrap_or_default();
+    let levels: Vec<String> = readings.iter().map(i32::to_string).collect();
+    let direction = observed_direction(&readings).map_or("null".to_string(), json_string);
+    let mut json = format!(
+        r#"{{"line":{},"levels":[{}],"direction":{},"verdict":"{}""#,
+        line_num,
+        levels.join(","),
+        direction,
+        if *verdict == Verdict::Safe {
+            "safe"
+        } else {
+            "unsafe"
+        }
+    );
+    if *verdict == Verdict::Unsafe {
+        match diagnose(&readings, policy) {
+            Err(v) => json.push_str(&format!(
+                r#","failing_index":{},"rule":{}"#,
+                v.index,
+                json_string(&v.rule.to_string())
+            )),
+            Ok(_) => json.push_str(r#","failing_index":null,"rule":null"#),
+        }
+    }
+    json.push('}');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{classify, Check};
+
+    #[test]
+    fn test_report_json() {
+        let policy = SafetyPolicy::default();
+        let json = |n, line: &str| {
+            report_json(n, line, &classify(line, n, &policy, Check::Plain), &policy)
+        };
+
+        assert_eq!(
+            json(1, "7 6 4 2 1"),
+            r#"{"line":1,"levels":[7,6,4,2,1],"direction":"decreasing","verdict":"safe"}"#
+        );
+        assert_eq!(
+            json(2, "1 2 7 8 9"),
+            r#"{"line":2,"levels":[1,2,7,8,9],"direction":"increasing","verdict":"unsafe","failing_index":2,"rule":"step too large"}"#
+        );
+        assert_eq!(
+            json(3, "1 \"x 3"),
+            r#"{"line":3,"levels":null,"direction":null,"verdict":"invalid","error":{"column":2,"token":"\"x"}}"#
+        );
+        assert_eq!(observed_direction(&[4, 4]), Some("flat"));
+        assert_eq!(observed_direction(&[4]), None);
+    }
+}
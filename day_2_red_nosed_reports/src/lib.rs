@@ -0,0 +1,290 @@
+pub mod diagnosis;
+pub mod generator;
+pub mod jsonl;
+pub mod parallel;
+pub mod policy;
+pub mod repair;
+pub mod runs;
+#[cfg(test)]
+mod testutil;
+pub mod tolerance;
+pub mod units;
+
+use policy::SafetyPolicy;
+use std::fmt;
+
+/// A level token that failed to parse. `column` is the byte offset of the
+/// token within its line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidLevel {
+    pub line: usize,
+    pub column: usize,
+    pub token: String,
+}
+
+impl fmt::Display for InvalidLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: invalid level `{}`",
+            self.line,
+            self.column + 1,
+            self.token
+        )
+    }
+}
+
+impl std::error::Error for InvalidLevel {}
+
+pub fn parse_levels_checked(line: &str, line_num: usize) -> Result<Vec<i32>, InvalidLevel> {
+    line.split_whitespace()
+        .map(|s| {
+            s.parse::<i32>().map_err(|_| InvalidLevel {
+                line: line_num,
+                column: s.as_ptr() as usize - line.as_ptr() as usize,
+                token: s.to_string(),
+            })
+        })
+        .collect()
+}
+
+pub fn parse_levels(line: &str) -> Option<Vec<i32>> {
+    parse_levels_checked(line, 0).ok()
+}
+
+/// Index of the second level of the first bad step in the given direction,
+/// ignoring the level at `skip` (if any).
+fn first_bad_step(
+    readings: &[i32],
+    policy: &SafetyPolicy,
+    skip: Option<usize>,
+    increasing: bool,
+) -> Option<usize> {
+    let mut kept = (0..readings.len()).filter(|&i| Some(i) != skip);
+    let mut prev = kept.next()?;
+    for i in kept {
+        if !policy.step_ok(readings[prev], readings[i], increasing) {
+            return Some(i);
+        }
+        prev = i;
+    }
+    None
+}
+
+pub fn levels_safe(readings: &[i32], policy: &SafetyPolicy) -> bool {
+    readings.len() >= 2
+        && policy
+            .directions()
+            .iter()
+            .any(|&increasing| first_bad_step(readings, policy, None, increasing).is_none())
+}
+
+/// Safe as given, or safe once any single level is removed. Any fix has to
+/// drop one of the two levels of the first bad step, so per direction at most
+/// two extra linear passes are needed.
+pub fn levels_safe_dampened(readings: &[i32], policy: &SafetyPolicy) -> bool {
+    if readings.len() < 2 {
+        return false;
+    }
+
+    policy.directions().iter().any(|&increasing| {
+        match first_bad_step(readings, policy, None, increasing) {
+            None => true,
+            Some(_) if readings.len() < 3 => false,
+            Some(i) => [i - 1, i]
+                .into_iter()
+                .any(|skip| first_bad_step(readings, policy, Some(skip), increasing).is_none()),
+        }
+    })
+}
+
+pub fn is_safe(line: &str) -> bool {
+    is_safe_with(line, &SafetyPolicy::default())
+}
+
+pub fn is_safe_with(line: &str, policy: &SafetyPolicy) -> bool {
+    parse_levels(line).is_some_and(|readings| levels_safe(&readings, policy))
+}
+
+pub fn is_safe_dampened(line: &str) -> bool {
+    is_safe_dampened_with(line, &SafetyPolicy::default())
+}
+
+pub fn is_safe_dampened_with(line: &str, policy: &SafetyPolicy) -> bool {
+    parse_levels(line).is_some_and(|readings| levels_safe_dampened(&readings, policy))
+}
+
+/// Which notion of "safe" a report is judged by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Check {
+    /// Safe as given.
+    #[default]
+    Plain,
+    /// Safe after removing at most one level.
+    Dampened,
+    /// Safe after removing at most this many levels.
+    Within(usize),
+}
+
+impl Check {
+    pub fn passes(&self, readings: &[i32], policy: &SafetyPolicy) -> bool {
+        match *self {
+            Check::Plain => levels_safe(readings, policy),
+            Check::Dampened => levels_safe_dampened(readings, policy),
+            Check::Within(k) => tolerance::safe_within(readings, policy, k).is_some(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Safe,
+    Unsafe,
+    /// The line could not be parsed, so it is neither safe nor unsafe.
+    Invalid(InvalidLevel),
+}
+
+pub fn classify(line: &str, line_num: usize, policy: &SafetyPolicy, check: Check) -> Verdict {
+    match parse_levels_checked(line, line_num) {
+        Ok(readings) if check.passes(&readings, policy) => Verdict::Safe,
+        Ok(_) => Verdict::Unsafe,
+        Err(e) => Verdict::Invalid(e),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub safe: usize,
+    pub unsafe_: usize,
+    pub invalid: usize,
+}
+
+impl Summary {
+    pub fn add(&mut self, verdict: &Verdict) {
+        match verdict {
+            Verdict::Safe => self.safe += 1,
+            Verdict::Unsafe => self.unsafe_ += 1,
+            Verdict::Invalid(_) => self.invalid += 1,
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "safe: {}, unsafe: {}, invalid: {}",
+            self.safe, self.unsafe_, self.invalid
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use policy::Direction;
+    use testutil::{single_removal_safe, Rng};
+
+    const EXAMPLE: [&str; 6] = [
+        "7 6 4 2 1",
+        "1 2 7 8 9",
+        "9 7 6 2 1",
+        "1 3 2 4 5",
+        "8 6 4 4 1",
+        "1 3 6 7 9",
+    ];
+
+    #[test]
+    fn test_is_safe() {
+        let safe: Vec<bool> = EXAMPLE.iter().map(|line| is_safe(line)).collect();
+        assert_eq!(safe, vec![true, false, false, false, false, true]);
+    }
+
+    #[test]
+    fn test_is_safe_dampened() {
+        let safe: Vec<bool> = EXAMPLE.iter().map(|line| is_safe_dampened(line)).collect();
+        assert_eq!(safe, vec![true, false, false, true, true, true]);
+        assert!(is_safe_dampened("5 1 2 3"));
+        assert!(is_safe_dampened("1 2 3 9"));
+        assert!(!is_safe_dampened("1 x 3"));
+    }
+
+    #[test]
+    fn test_classify() {
+        let policy = SafetyPolicy::default();
+        let lines = ["7 6 4 2 1", "1 2 7 8 9", "1 3 x4 5", "1 3 2 4 5"];
+        let mut summary = Summary::default();
+        for (i, line) in lines.iter().enumerate() {
+            summary.add(&classify(line, i + 1, &policy, Check::Plain));
+        }
+        assert_eq!(
+            summary,
+            Summary {
+                safe: 1,
+                unsafe_: 2,
+                invalid: 1
+            }
+        );
+        assert_eq!(
+            classify("1 3 x4 5", 3, &policy, Check::Dampened),
+            Verdict::Invalid(InvalidLevel {
+                line: 3,
+                column: 4,
+                token: "x4".to_string()
+            })
+        );
+        assert_eq!(
+            classify("1 3 2 4 5", 4, &policy, Check::Dampened),
+            Verdict::Safe
+        );
+    }
+
+    #[test]
+    fn test_custom_policy() {
+        let policy = SafetyPolicy {
+            min_step: 2,
+            max_step: 5,
+            direction: Direction::Increasing,
+            allow_equal: true,
+        };
+        assert!(is_safe_with("1 3 3 8 10", &policy));
+        assert!(!is_safe_with("1 2 4", &policy));
+        assert!(!is_safe_with("9 7 5", &policy));
+        assert!(is_safe_dampened_with("1 2 4", &policy));
+    }
+
+    #[test]
+    fn test_dampened_matches_brute_force() {
+        let mut rng = Rng::new(0x9e37_79b9_7f4a_7c15);
+        let mut next = |bound: u64| rng.below(bound);
+        let policies = [
+            SafetyPolicy::default(),
+            SafetyPolicy {
+                min_step: 2,
+                max_step: 4,
+                direction: Direction::Decreasing,
+                allow_equal: true,
+            },
+        ];
+
+        for _ in 0..20_000 {
+            let len = 1 + next(9) as usize;
+            let mut level = next(20) as i32;
+            let readings: Vec<i32> = (0..len)
+                .map(|_| {
+                    level += next(11) as i32 - 5;
+                    level
+                })
+                .collect();
+            for policy in &policies {
+                assert_eq!(
+                    levels_safe_dampened(&readings, policy),
+                    single_removal_safe(&readings, policy),
+                    "{:?} {:?}",
+                    readings,
+                    policy
+                );
+            }
+        }
+    }
+}
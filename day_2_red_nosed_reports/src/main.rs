@@ -1,40 +1,228 @@
+use day_2_red_nosed_reports::diagnosis::diagnose;
+use day_2_red_nosed_reports::generator::{Generator, GeneratorConfig};
+use day_2_red_nosed_reports::jsonl::report_json;
+use day_2_red_nosed_reports::parallel::classify_parallel;
+use day_2_red_nosed_reports::policy::{PolicySet, SafetyPolicy};
+use day_2_red_nosed_reports::repair::minimal_repair;
+use day_2_red_nosed_reports::runs::{longest_safe_window, LevelError, ReportReader};
+use day_2_red_nosed_reports::tolerance::min_removals;
+use day_2_red_nosed_reports::units::{classify_units, UnitPolicy};
+use day_2_red_nosed_reports::{classify, parse_levels, Check, Summary, Verdict};
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
+use std::process;
 
-fn is_safe(line: &str) -> bool {
-    let readings: Vec<i32> = match line.split_whitespace().map(|s| s.parse::<i32>()).collect() {
-        Ok(v) => v,
-        Err(_) => return false,
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("error occurred: {}", message);
+    process::exit(1);
+}
+
+/// Prints the longest safe window of every report, reading levels as a stream.
+/// Blank lines are skipped; with `strict`, the first invalid report fails the
+/// run.
+fn longest_runs<R: BufRead>(reader: R, policy: &SafetyPolicy, strict: bool) -> io::Result<()> {
+    let mut reports = ReportReader::new(reader);
+    while let Some(mut levels) = reports.next_report()? {
+        let line = levels.line;
+        let mut seen = 0;
+        let (longest, count) = longest_safe_window(levels.by_ref().inspect(|_| seen += 1), policy);
+        match levels.finish() {
+            Some(LevelError::Io(e)) => return Err(e),
+            Some(LevelError::Invalid(e)) if strict => fail(&e.to_string()),
+            Some(LevelError::Invalid(e)) => eprintln!("{}", e),
+            None if seen == 0 => {}
+            None => match longest {
+                Some(w) => println!(
+                    "line {}: longest safe window starts at {}, {} levels, {:?} ({} windows)",
+                    line, w.start, w.len, w.direction, count
+                ),
+                None => println!("line {}: no safe window", line),
+            },
+        }
+    }
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let explain = args.iter().any(|a| a == "--explain");
+    let removals = args.iter().any(|a| a == "--removals");
+    let strict = args.iter().any(|a| a == "--strict");
+    let repair = args.iter().any(|a| a == "--repair");
+    let jsonl = match arg_value(&args, "--format") {
+        Some("jsonl") => true,
+        Some("count") | None => false,
+        Some(other) => fail(&format!("unknown output format `{}`", other)),
+    };
+    // JSON Lines output must stay one object per line, so the plain-text
+    // per-report extras cannot be mixed into it.
+    if jsonl {
+        let text_only = ["--explain", "--repair", "--removals", "--runs"];
+        if let Some(flag) = text_only.iter().find(|f| args.iter().any(|a| a == *f)) {
+            fail(&format!("--format jsonl cannot be combined with {}", flag));
+        }
+    }
+    let check = match arg_value(&args, "--tolerance").map(str::parse::<usize>) {
+        Some(Ok(k)) => Check::Within(k),
+        Some(Err(_)) => fail("--tolerance expects a number of levels"),
+        None if args.iter().any(|a| a == "--dampened") => Check::Dampened,
+        None => Check::Plain,
+    };
+    let policy = match arg_value(&args, "--policy") {
+        Some(path) => {
+            let family = arg_value(&args, "--family").unwrap_or("default");
+            PolicySet::load(path)
+                .and_then(|set| set.get(family))
+                .unwrap_or_else(|e| fail(&e.to_string()))
+        }
+        None => SafetyPolicy::default(),
     };
 
-    if readings.len() < 2 {
-        return false;
+    let units = arg_value(&args, "--units").map(|range| {
+        UnitPolicy::parse(range, &policy).unwrap_or_else(|e| fail(&format!("--units: {}", e)))
+    });
+    // These report levels and steps back as integers, which would be on the
+    // rescaled grid rather than in the readings' own units.
+    if units.is_some() {
+        let integer_only = [
+            "--explain",
+            "--removals",
+            "--repair",
+            "--runs",
+            "--threads",
+            "--format",
+        ];
+        if let Some(flag) = integer_only.iter().find(|f| args.iter().any(|a| a == *f)) {
+            fail(&format!("--units cannot be combined with {}", flag));
+        }
     }
 
-    let first_diff = readings[1] - readings[0];
-    let is_increasing = first_diff > 0;
+    if let Some(count) = arg_value(&args, "--generate") {
+        let count = count
+            .parse()
+            .unwrap_or_else(|_| fail("--generate expects a number of reports"));
+        let mut config = GeneratorConfig {
+            policy,
+            ..GeneratorConfig::default()
+        };
+        if let Some(seed) = arg_value(&args, "--seed") {
+            config.seed = seed
+                .parse()
+                .unwrap_or_else(|_| fail("--seed expects a number"));
+        }
+        let mut safe = 0;
+        for report in Generator::new(config).take(count) {
+            safe += usize::from(report.expected_safe);
+            println!("{}", report.line());
+        }
+        eprintln!("expected safe: {} of {}", safe, count);
+        return Ok(());
+    }
 
-    for i in 1..readings.len() {
-        let diff = readings[i] - readings[i - 1];
-        if diff.abs() > 3 || (is_increasing && diff <= 0) || (!is_increasing && diff >= 0) {
-            return false;
+    if let Some(workers) = arg_value(&args, "--threads") {
+        // Workers only tally verdicts; per-report output needs the single
+        // sequential pass below.
+        let per_report = ["--explain", "--removals", "--repair", "--runs"];
+        if let Some(flag) = per_report.iter().find(|f| args.iter().any(|a| a == *f)) {
+            fail(&format!("--threads cannot be combined with {}", flag));
+        }
+        if jsonl {
+            fail("--threads cannot be combined with --format jsonl");
+        }
+        let workers = workers
+            .parse()
+            .unwrap_or_else(|_| fail("--threads expects a number of workers"));
+        let classified = classify_parallel("input", &policy, check, workers)?;
+        if let Some(e) = classified.invalid.first().filter(|_| strict) {
+            fail(&e.to_string());
         }
+        for e in &classified.invalid {
+            eprintln!("{}", e);
+        }
+        println!("{}", classified.summary.safe);
+        eprintln!("{}", classified.summary);
+        return Ok(());
     }
-    true
-}
 
-fn main() -> io::Result<()> {
     let file = File::open("input")?;
     let reader = BufReader::new(file);
-    let mut safe_count = 0;
 
-    for line in reader.lines() {
+    if args.iter().any(|a| a == "--runs") {
+        return longest_runs(reader, &policy, strict);
+    }
+
+    let mut summary = Summary::default();
+
+    for (i, line) in reader.lines().enumerate() {
         let line = line?;
-        if !line.trim().is_empty() && is_safe(&line) {
-            safe_count += 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let verdict = match &units {
+            Some(units) => classify_units(&line, i + 1, units, check),
+            None => classify(&line, i + 1, &policy, check),
+        };
+        summary.add(&verdict);
+        if jsonl {
+            println!("{}", report_json(i + 1, &line, &verdict, &policy));
+        }
+
+        match &verdict {
+            Verdict::Invalid(e) if strict => fail(&e.to_string()),
+            Verdict::Invalid(e) => eprintln!("{}: {}", e, line),
+            Verdict::Unsafe if explain => {
+                let readings = parse_levels(&line).unwrap_or_default();
+                let reason = match diagnose(&readings, &policy) {
+                    Err(violation) => violation.to_string(),
+                    // Safe as given, so only a tolerance check can disagree.
+                    Ok(_) => "unsafe after tolerance".to_string(),
+                };
+                println!("line {}: {}: {}", i + 1, reason, line);
+            }
+            _ => {}
+        }
+
+        if repair && verdict == Verdict::Unsafe {
+            let readings = parse_levels(&line).unwrap_or_default();
+            match minimal_repair(&readings, &policy) {
+                Some(fix) => {
+                    let changes: Vec<String> = fix
+                        .changes
+                        .iter()
+                        .map(|(index, old, new)| format!("[{}] {} -> {}", index, old, new))
+                        .collect();
+                    println!("line {}: change {}: {}", i + 1, changes.join(", "), line);
+                }
+                None => println!("line {}: cannot be repaired: {}", i + 1, line),
+            }
+        }
+
+        if removals && !matches!(verdict, Verdict::Invalid(_)) {
+            let readings = parse_levels(&line).unwrap_or_default();
+            match min_removals(&readings, &policy) {
+                Some(removal) if removal.count() > 0 => println!(
+                    "line {}: remove {} level(s) at {:?}: {}",
+                    i + 1,
+                    removal.count(),
+                    removal.removed,
+                    line
+                ),
+                Some(_) => {}
+                None => println!("line {}: no safe subsequence: {}", i + 1, line),
+            }
         }
     }
 
-    println!("{}", safe_count);
+    if !jsonl {
+        println!("{}", summary.safe);
+    }
+    eprintln!("{}", summary);
     Ok(())
 }
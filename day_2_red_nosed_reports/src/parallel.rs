@@ -0,0 +1,160 @@
+//! Multi-threaded classification of large report files using only std.
+//!
+//! The file is split into byte ranges of roughly equal size, each moved forward
+//! to the next line start, and every range is classified on its own scoped
+//! thread with its own file handle. Per-chunk line counts are used afterwards to
+//! turn chunk-relative line numbers back into file line numbers.
+
+use crate::policy::SafetyPolicy;
+use crate::{classify, Check, InvalidLevel, Summary, Verdict};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::thread;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Classified {
+    pub summary: Summary,
+    /// Invalid reports in file order.
+    pub invalid: Vec<InvalidLevel>,
+    /// Lines read, including blank ones.
+    pub lines: usize,
+}
+
+impl Classified {
+    fn append(&mut self, mut other: Classified) {
+        for e in &mut other.invalid {
+            e.line += self.lines;
+        }
+        self.summary.safe += other.summary.safe;
+        self.summary.unsafe_ += other.summary.unsafe_;
+        self.summary.invalid += other.summary.invalid;
+        self.invalid.append(&mut other.invalid);
+        self.lines += other.lines;
+    }
+}
+
+/// Classifies every non-blank line of `reader`, numbering lines from 1.
+pub fn classify_reader<R: BufRead>(
+    reader: R,
+    policy: &SafetyPolicy,
+    check: Check,
+) -> io::Result<Classified> {
+    let mut classified = Classified::default();
+    for line in reader.lines() {
+        let line = line?;
+        classified.lines += 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let verdict = classify(&line, classified.lines, policy, check);
+        classified.summary.add(&verdict);
+        if let Verdict::Invalid(e) = verdict {
+            classified.invalid.push(e);
+        }
+    }
+    Ok(classified)
+}
+
+/// Offset of the first line starting at or after `offset`.
+fn next_line_start(file: &mut File, offset: u64, len: u64) -> io::Result<u64> {
+    if offset == 0 || offset >= len {
+        return Ok(offset.min(len));
+    }
+    // The line containing `offset - 1` belongs to the previous chunk.
+    file.seek(SeekFrom::Start(offset - 1))?;
+    let mut skipped = Vec::new();
+    BufReader::new(file).read_until(b'\n', &mut skipped)?;
+    Ok(offset - 1 + skipped.len() as u64)
+}
+
+/// Line-aligned `(start, end)` byte ranges covering the whole file.
+fn chunk_bounds(path: &Path, chunks: usize) -> io::Result<Vec<(u64, u64)>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let chunks = chunks.max(1) as u64;
+    let mut starts = Vec::new();
+    for i in 0..chunks {
+        starts.push(next_line_start(&mut file, len * i / chunks, len)?);
+    }
+    starts.push(len);
+    starts.dedup();
+    Ok(starts.windows(2).map(|w| (w[0], w[1])).collect())
+}
+
+/// Classifies the file on `workers` threads. The result is identical to
+/// `classify_reader` over the whole file.
+pub fn classify_parallel<P: AsRef<Path>>(
+    path: P,
+    policy: &SafetyPolicy,
+    check: Check,
+    workers: usize,
+) -> io::Result<Classified> {
+    let path = path.as_ref();
+    let bounds = chunk_bounds(path, workers)?;
+
+    let results: Vec<io::Result<Classified>> = thread::scope(|scope| {
+        let handles: Vec<_> = bounds
+            .iter()
+            .map(|&(start, end)| {
+                scope.spawn(move || {
+                    let mut file = File::open(path)?;
+                    file.seek(SeekFrom::Start(start))?;
+                    classify_reader(BufReader::new(file.take(end - start)), policy, check)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("classification worker panicked"))
+            .collect()
+    });
+
+    let mut classified = Classified::default();
+    for chunk in results {
+        classified.append(chunk?);
+    }
+    Ok(classified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::Rng;
+    use std::io::Write;
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let mut rng = Rng::new(0x5eed_cafe_0123_4567);
+        let mut next = |bound: u64| rng.below(bound);
+        let mut text = String::new();
+        for i in 0..2_000 {
+            match i % 97 {
+                0 => text.push('\n'),
+                1 => text.push_str("1 2 x 4\n"),
+                _ => {
+                    let levels: Vec<String> = (0..2 + next(6))
+                        .map(|_| (next(9) + i % 3).to_string())
+                        .collect();
+                    text.push_str(&levels.join(" "));
+                    text.push('\n');
+                }
+            }
+        }
+        text.push_str("7 6 4"); // no trailing newline
+
+        let path = std::env::temp_dir().join(format!("reports-{}.txt", std::process::id()));
+        File::create(&path)
+            .unwrap()
+            .write_all(text.as_bytes())
+            .unwrap();
+
+        let policy = SafetyPolicy::default();
+        let expected = classify_reader(text.as_bytes(), &policy, Check::Dampened).unwrap();
+        for workers in [1, 2, 3, 8, 64] {
+            let parallel = classify_parallel(&path, &policy, Check::Dampened, workers).unwrap();
+            assert_eq!(parallel, expected, "workers={}", workers);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,233 @@
+//! Safety rules for a report, loadable per sensor family from a config file.
+//!
+//! ```text
+//! # thresholds for everything not listed below
+//! [default]
+//! min_step = 1
+//! max_step = 3
+//! direction = either      # increasing | decreasing | either
+//! allow_equal = false
+//!
+//! [thermal]
+//! max_step = 5
+//! ```
+//!
+//! A section starts from the built-in defaults and overrides only the keys it
+//! sets. Keys before the first section header belong to `default`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Increasing,
+    Decreasing,
+    Either,
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "increasing" => Ok(Direction::Increasing),
+            "decreasing" => Ok(Direction::Decreasing),
+            "either" => Ok(Direction::Either),
+            _ => Err(format!("unknown direction `{}`", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SafetyPolicy {
+    /// Smallest allowed non-zero step, as an absolute difference.
+    pub min_step: i32,
+    /// Largest allowed step, as an absolute difference.
+    pub max_step: i32,
+    pub direction: Direction,
+    /// Whether two equal adjacent levels are acceptable. An equal pair does not
+    /// count against `min_step` or the direction.
+    pub allow_equal: bool,
+}
+
+/// The original rules: strictly monotonic, steps between 1 and 3.
+impl Default for SafetyPolicy {
+    fn default() -> Self {
+        SafetyPolicy {
+            min_step: 1,
+            max_step: 3,
+            direction: Direction::Either,
+            allow_equal: false,
+        }
+    }
+}
+
+impl SafetyPolicy {
+    /// The fixed directions a report may follow, as "is increasing" flags.
+    pub fn directions(&self) -> &'static [bool] {
+        match self.direction {
+            Direction::Increasing => &[true],
+            Direction::Decreasing => &[false],
+            Direction::Either => &[true, false],
+        }
+    }
+
+    /// Whether `prev -> next` is an acceptable step for a report going in the
+    /// given direction.
+    pub fn step_ok(&self, prev: i32, next: i32, increasing: bool) -> bool {
+        let diff = next as i64 - prev as i64;
+        if diff == 0 {
+            return self.allow_equal;
+        }
+        let magnitude = diff.abs();
+        (diff > 0) == increasing
+            && magnitude >= self.min_step as i64
+            && magnitude <= self.max_step as i64
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let int = |v: &str| {
+            v.parse::<i32>()
+                .map_err(|_| format!("`{}` expects an integer, got `{}`", key, v))
+        };
+        match key {
+            "min_step" => self.min_step = int(value)?,
+            "max_step" => self.max_step = int(value)?,
+            "direction" => self.direction = value.parse()?,
+            "allow_equal" => {
+                self.allow_equal = value
+                    .parse()
+                    .map_err(|_| format!("`{}` expects true or false, got `{}`", key, value))?
+            }
+            _ => return Err(format!("unknown key `{}`", key)),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum PolicyError {
+    Io(io::Error),
+    Syntax { line: usize, message: String },
+    Invalid(String),
+    UnknownFamily(String),
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyError::Io(e) => write!(f, "policy file cannot be read: {}", e),
+            PolicyError::Syntax { line, message } => {
+                write!(f, "policy file line {}: {}", line, message)
+            }
+            PolicyError::Invalid(message) => write!(f, "invalid policy: {}", message),
+            PolicyError::UnknownFamily(name) => write!(f, "no policy for sensor family `{}`", name),
+        }
+    }
+}
+
+impl std::error::Error for PolicyError {}
+
+/// Policies by sensor family name. `default` is always present.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicySet {
+    families: HashMap<String, SafetyPolicy>,
+}
+
+impl PolicySet {
+    pub fn parse(text: &str) -> Result<PolicySet, PolicyError> {
+        let mut families = HashMap::from([("default".to_string(), SafetyPolicy::default())]);
+        let mut current = "default".to_string();
+
+        for (i, raw) in text.lines().enumerate() {
+            let syntax = |message: String| PolicyError::Syntax {
+                line: i + 1,
+                message,
+            };
+            let line = raw.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                current = name.trim().to_string();
+                families.entry(current.clone()).or_default();
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| syntax(format!("expected `key = value`, got `{}`", line)))?;
+            let policy = families.entry(current.clone()).or_default();
+            policy.set(key.trim(), value.trim()).map_err(syntax)?;
+        }
+
+        for (name, policy) in &families {
+            if policy.min_step < 1 || policy.min_step > policy.max_step {
+                return Err(PolicyError::Invalid(format!(
+                    "[{}] needs 1 <= min_step <= max_step, got {}..{}",
+                    name, policy.min_step, policy.max_step
+                )));
+            }
+        }
+
+        Ok(PolicySet { families })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<PolicySet, PolicyError> {
+        let text = fs::read_to_string(path).map_err(PolicyError::Io)?;
+        PolicySet::parse(&text)
+    }
+
+    pub fn get(&self, family: &str) -> Result<SafetyPolicy, PolicyError> {
+        self.families
+            .get(family)
+            .copied()
+            .ok_or_else(|| PolicyError::UnknownFamily(family.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_policy_set() {
+        let text = "max_step = 4\n\n[thermal] # noisy\nmin_step = 2\nmax_step = 6\ndirection = increasing\nallow_equal = true\n";
+        let set = PolicySet::parse(text).unwrap();
+        assert_eq!(
+            set.get("default").unwrap(),
+            SafetyPolicy {
+                max_step: 4,
+                ..SafetyPolicy::default()
+            }
+        );
+        assert_eq!(
+            set.get("thermal").unwrap(),
+            SafetyPolicy {
+                min_step: 2,
+                max_step: 6,
+                direction: Direction::Increasing,
+                allow_equal: true,
+            }
+        );
+        assert!(matches!(
+            set.get("pressure"),
+            Err(PolicyError::UnknownFamily(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = PolicySet::parse("[a]\nmax_step = three\n").unwrap_err();
+        assert!(matches!(err, PolicyError::Syntax { line: 2, .. }));
+        let err = PolicySet::parse("direction = up\n").unwrap_err();
+        assert!(matches!(err, PolicyError::Syntax { line: 1, .. }));
+        let err = PolicySet::parse("min_step = 5\n").unwrap_err();
+        assert!(err.to_string().contains("min_step <= max_step"));
+    }
+}
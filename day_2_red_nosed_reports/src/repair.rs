@@ -0,0 +1,205 @@
+//! Minimal-change repair: the fewest level values to replace (not remove) so a
+//! report satisfies the policy, with suggested replacement values.
+//!
+//! Levels that are kept must be reachable from one another: between two kept
+//! levels `g` positions apart there are `g` steps, and the difference between
+//! them has to be expressible as `g` allowed steps. A DP over kept levels finds
+//! the largest such set; the changed levels in between are then interpolated.
+
+use crate::levels_safe;
+use crate::policy::SafetyPolicy;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    /// `(index, old value, new value)` for every changed level, ascending.
+    pub changes: Vec<(usize, i32, i32)>,
+    /// The report with the changes applied; it passes `levels_safe`.
+    pub repaired: Vec<i32>,
+    pub increasing: bool,
+}
+
+/// Number of non-zero steps to use for moving `total` (already signed in the
+/// report's direction) over `steps` steps, if it can be done at all.
+fn nonzero_steps(policy: &SafetyPolicy, total: i64, steps: i64) -> Option<i64> {
+    let (min, max) = (policy.min_step as i64, policy.max_step as i64);
+    if total >= steps * min && total <= steps * max {
+        return Some(steps);
+    }
+    if !policy.allow_equal || total < 0 {
+        return None;
+    }
+    // Equal steps are free, so use the fewest non-zero steps that can cover it.
+    let m = (total + max - 1) / max;
+    (m <= steps && total >= m * min).then_some(m)
+}
+
+/// Fills the levels strictly between kept indices `from` and `to`.
+fn interpolate(
+    policy: &SafetyPolicy,
+    values: &mut [i64],
+    from: usize,
+    to: usize,
+    sign: i64,
+) -> Option<()> {
+    let steps = (to - from) as i64;
+    let total = (values[to] - values[from]) * sign;
+    let m = nonzero_steps(policy, total, steps)?;
+    let mut level = values[from];
+    for k in 0..steps - 1 {
+        if k < m {
+            // Spread the total as evenly as possible over the m moving steps.
+            level += sign * (total / m + i64::from(k < total % m));
+        }
+        values[from + 1 + k as usize] = level;
+    }
+    Some(())
+}
+
+fn repair_direction(readings: &[i32], policy: &SafetyPolicy, increasing: bool) -> Option<Repair> {
+    let n = readings.len();
+    let sign = if increasing { 1 } else { -1 };
+    let reachable = |j: usize, i: usize| {
+        let total = (readings[i] as i64 - readings[j] as i64) * sign;
+        nonzero_steps(policy, total, (i - j) as i64).is_some()
+    };
+
+    let mut kept = vec![1usize; n];
+    let mut parent = vec![usize::MAX; n];
+    for i in 0..n {
+        for j in 0..i {
+            if kept[j] + 1 > kept[i] && reachable(j, i) {
+                kept[i] = kept[j] + 1;
+                parent[i] = j;
+            }
+        }
+    }
+    let last = (0..n).max_by_key(|&i| (kept[i], std::cmp::Reverse(i)))?;
+
+    let mut keep = Vec::new();
+    let mut i = last;
+    while i != usize::MAX {
+        keep.push(i);
+        i = parent[i];
+    }
+    keep.reverse();
+
+    let mut values: Vec<i64> = readings.iter().map(|&v| v as i64).collect();
+    for pair in keep.windows(2) {
+        interpolate(policy, &mut values, pair[0], pair[1], sign)?;
+    }
+    let step = sign * policy.min_step as i64;
+    for i in (0..keep[0]).rev() {
+        values[i] = values[i + 1] - step;
+    }
+    for i in last + 1..n {
+        values[i] = values[i - 1] + step;
+    }
+
+    let repaired = values
+        .iter()
+        .map(|&v| i32::try_from(v).ok())
+        .collect::<Option<Vec<i32>>>()?;
+    let changes = (0..n)
+        .filter(|&i| repaired[i] != readings[i])
+        .map(|i| (i, readings[i], repaired[i]))
+        .collect();
+    Some(Repair {
+        changes,
+        repaired,
+        increasing,
+    })
+}
+
+/// Fewest value changes that make the report safe. `None` for reports shorter
+/// than two levels, which no change of values can fix.
+pub fn minimal_repair(readings: &[i32], policy: &SafetyPolicy) -> Option<Repair> {
+    if readings.len() < 2 {
+        return None;
+    }
+    policy
+        .directions()
+        .iter()
+        .filter_map(|&increasing| repair_direction(readings, policy, increasing))
+        .filter(|repair| levels_safe(&repair.repaired, policy))
+        .min_by_key(|repair| repair.changes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Direction;
+    use crate::testutil::Rng;
+
+    /// Fewest changes found by trying replacement values from a small range.
+    fn brute_force(readings: &[i32], policy: &SafetyPolicy) -> usize {
+        let n = readings.len();
+        let candidates: Vec<i32> = (-12..=18).collect();
+        for changed in 0..n {
+            for mask in (0u32..1 << n).filter(|m| m.count_ones() as usize == changed) {
+                let slots: Vec<usize> = (0..n).filter(|&i| mask & (1 << i) != 0).collect();
+                let mut report = readings.to_vec();
+                let mut odometer = vec![0usize; slots.len()];
+                loop {
+                    for (slot, &c) in slots.iter().zip(&odometer) {
+                        report[*slot] = candidates[c];
+                    }
+                    if levels_safe(&report, policy) {
+                        return changed;
+                    }
+                    let Some(k) = odometer.iter().position(|&c| c + 1 < candidates.len()) else {
+                        break;
+                    };
+                    odometer[k] += 1;
+                    odometer[..k].fill(0);
+                }
+            }
+        }
+        n
+    }
+
+    #[test]
+    fn test_minimal_repair() {
+        let policy = SafetyPolicy::default();
+        let repair = minimal_repair(&[1, 2, 7, 8, 9], &policy).unwrap();
+        assert_eq!(repair.changes.len(), 1);
+        assert!(levels_safe(&repair.repaired, &policy));
+
+        let repair = minimal_repair(&[10, 10, 10, 10], &policy).unwrap();
+        assert_eq!(repair.changes.len(), 3);
+
+        let repair = minimal_repair(&[1, 20, 3, 4], &policy).unwrap();
+        assert_eq!(repair.changes, vec![(1, 20, 2)]);
+        assert!(minimal_repair(&[5], &policy).is_none());
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut rng = Rng::new(0xdead_beef_cafe_f00d);
+        let mut next = |bound: u64| rng.below(bound);
+        let policies = [
+            SafetyPolicy::default(),
+            SafetyPolicy {
+                min_step: 2,
+                max_step: 3,
+                direction: Direction::Decreasing,
+                allow_equal: true,
+            },
+        ];
+
+        for _ in 0..300 {
+            let len = 2 + next(3) as usize;
+            let readings: Vec<i32> = (0..len).map(|_| next(7) as i32).collect();
+            for policy in &policies {
+                let repair = minimal_repair(&readings, policy).unwrap();
+                assert!(levels_safe(&repair.repaired, policy));
+                assert_eq!(
+                    repair.changes.len(),
+                    brute_force(&readings, policy),
+                    "{:?} {:?}",
+                    readings,
+                    policy
+                );
+            }
+        }
+    }
+}
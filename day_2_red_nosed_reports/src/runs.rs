@@ -0,0 +1,358 @@
+//! Maximal contiguous safe windows of long reports, computed on streamed
+//! levels.
+//!
+//! One run is tracked per allowed direction; a run closes at the first step
+//! that breaks it. A closed run is reported unless the other direction's run
+//! covers it (possible only through equal steps when `allow_equal` is set).
+//! Memory is O(1) in the report length; `ReportReader` supplies levels straight
+//! from a `BufRead` so a report never has to be held as a whole line.
+
+use crate::policy::SafetyPolicy;
+use crate::InvalidLevel;
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunDirection {
+    Increasing,
+    Decreasing,
+    /// Every step in the window is zero.
+    Flat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window {
+    pub start: usize,
+    pub len: usize,
+    pub direction: RunDirection,
+}
+
+/// Iterator over the maximal safe windows of a stream of levels, in order of
+/// where they end.
+pub struct SafeWindows<I> {
+    levels: I,
+    policy: SafetyPolicy,
+    /// `(increasing, start of the open run)` per allowed direction.
+    runs: Vec<(bool, usize)>,
+    prev: Option<i32>,
+    index: usize,
+    pending: VecDeque<Window>,
+    finished: bool,
+}
+
+pub fn safe_windows<I>(levels: I, policy: &SafetyPolicy) -> SafeWindows<I::IntoIter>
+where
+    I: IntoIterator<Item = i32>,
+{
+    SafeWindows {
+        levels: levels.into_iter(),
+        policy: *policy,
+        runs: policy.directions().iter().map(|&d| (d, 0)).collect(),
+        prev: None,
+        index: 0,
+        pending: VecDeque::new(),
+        finished: false,
+    }
+}
+
+impl<I> SafeWindows<I> {
+    /// Closes every run whose `ok` flag is false at `end`, the index of its last
+    /// level.
+    fn close(&mut self, end: usize, ok: &[bool]) {
+        for k in 0..self.runs.len() {
+            let (increasing, start) = self.runs[k];
+            if ok[k] || end <= start {
+                continue;
+            }
+            let mut direction = if increasing {
+                RunDirection::Increasing
+            } else {
+                RunDirection::Decreasing
+            };
+            if let Some(o) = (0..self.runs.len()).find(|&o| o != k) {
+                let other = self.runs[o].1;
+                if (ok[o] && other <= start) || (!ok[o] && other < start) {
+                    continue;
+                }
+                if !ok[o] && other == start {
+                    // Both runs span the same levels, so every step was zero.
+                    if k > o {
+                        continue;
+                    }
+                    direction = RunDirection::Flat;
+                }
+            }
+            self.pending.push_back(Window {
+                start,
+                len: end - start + 1,
+                direction,
+            });
+        }
+    }
+}
+
+impl<I: Iterator<Item = i32>> Iterator for SafeWindows<I> {
+    type Item = Window;
+
+    fn next(&mut self) -> Option<Window> {
+        loop {
+            if let Some(window) = self.pending.pop_front() {
+                return Some(window);
+            }
+            if self.finished {
+                return None;
+            }
+
+            let Some(level) = self.levels.next() else {
+                self.finished = true;
+                if self.index > 0 {
+                    let none_ok = vec![false; self.runs.len()];
+                    self.close(self.index - 1, &none_ok);
+                }
+                continue;
+            };
+
+            let i = self.index;
+            self.index += 1;
+            if let Some(prev) = self.prev.replace(level) {
+                let ok: Vec<bool> = self
+                    .runs
+                    .iter()
+                    .map(|&(increasing, _)| self.policy.step_ok(prev, level, increasing))
+                    .collect();
+                self.close(i - 1, &ok);
+                for (run, ok) in self.runs.iter_mut().zip(ok) {
+                    if !ok {
+                        run.1 = i;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The longest window (earliest on ties) and the number of windows seen.
+pub fn longest_safe_window<I>(levels: I, policy: &SafetyPolicy) -> (Option<Window>, usize)
+where
+    I: IntoIterator<Item = i32>,
+{
+    safe_windows(levels, policy).fold((None, 0), |(best, count), w| {
+        let better = best.is_none_or(|b: Window| w.len > b.len);
+        (if better { Some(w) } else { best }, count + 1)
+    })
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(io::Error),
+    Invalid(InvalidLevel),
+}
+
+/// Reads reports one level at a time, without buffering whole lines.
+pub struct ReportReader<R> {
+    reader: R,
+    line: usize,
+}
+
+impl<R: BufRead> ReportReader<R> {
+    pub fn new(reader: R) -> Self {
+        ReportReader { reader, line: 0 }
+    }
+
+    /// Levels of the next report, or `None` at end of input.
+    pub fn next_report(&mut self) -> io::Result<Option<Levels<'_, R>>> {
+        if self.reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        self.line += 1;
+        Ok(Some(Levels {
+            reader: &mut self.reader,
+            line: self.line,
+            column: 0,
+            at_eol: false,
+            error: None,
+        }))
+    }
+}
+
+/// Levels of a single report. Iteration stops at end of line, or at the first
+/// bad token or read error, which is then kept in `error`.
+pub struct Levels<'a, R> {
+    reader: &'a mut R,
+    pub line: usize,
+    column: usize,
+    at_eol: bool,
+    pub error: Option<LevelError>,
+}
+
+impl<R: BufRead> Levels<'_, R> {
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        let byte = self.reader.fill_buf()?.first().copied();
+        if byte.is_some() {
+            self.reader.consume(1);
+            self.column += 1;
+        }
+        if matches!(byte, None | Some(b'\n')) {
+            self.at_eol = true;
+        }
+        Ok(byte)
+    }
+
+    fn next_token(&mut self) -> io::Result<Option<(usize, Vec<u8>)>> {
+        let mut token = Vec::new();
+        let mut start = self.column;
+        while !self.at_eol {
+            match self.next_byte()? {
+                Some(b) if !b.is_ascii_whitespace() => token.push(b),
+                _ if !token.is_empty() => break,
+                _ => start = self.column,
+            }
+        }
+        Ok((!token.is_empty()).then_some((start, token)))
+    }
+
+    /// Skips whatever is left of the line and returns the error, if any.
+    pub fn finish(mut self) -> Option<LevelError> {
+        while !self.at_eol {
+            if let Err(e) = self.next_byte() {
+                return Some(LevelError::Io(e));
+            }
+        }
+        self.error
+    }
+}
+
+impl<R: BufRead> Iterator for Levels<'_, R> {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<i32> {
+        if self.error.is_some() {
+            return None;
+        }
+        let (column, token) = match self.next_token() {
+            Ok(token) => token?,
+            Err(e) => {
+                self.error = Some(LevelError::Io(e));
+                return None;
+            }
+        };
+        let parsed = std::str::from_utf8(&token)
+            .ok()
+            .and_then(|s| s.parse::<i32>().ok());
+        if parsed.is_none() {
+            self.error = Some(LevelError::Invalid(InvalidLevel {
+                line: self.line,
+                column,
+                token: String::from_utf8_lossy(&token).into_owned(),
+            }));
+        }
+        parsed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels_safe;
+    use crate::policy::Direction;
+    use crate::testutil::Rng;
+
+    /// Every interval that is safe and cannot be extended either way.
+    fn brute_force(readings: &[i32], policy: &SafetyPolicy) -> Vec<(usize, usize)> {
+        let n = readings.len();
+        let safe = |s: usize, e: usize| levels_safe(&readings[s..=e], policy);
+        let mut windows: Vec<(usize, usize)> = (0..n)
+            .flat_map(|s| (s + 1..n).map(move |e| (s, e)))
+            .filter(|&(s, e)| safe(s, e))
+            .filter(|&(s, e)| {
+                let grows_left = s > 0 && safe(s - 1, e);
+                let grows_right = e + 1 < n && safe(s, e + 1);
+                !grows_left && !grows_right
+            })
+            .map(|(s, e)| (s, e - s + 1))
+            .collect();
+        windows.sort_unstable();
+        windows
+    }
+
+    #[test]
+    fn test_safe_windows() {
+        let policy = SafetyPolicy::default();
+        let windows: Vec<Window> = safe_windows([1, 2, 3, 2, 1, 1, 9, 10], &policy).collect();
+        let spans: Vec<(usize, usize)> = windows.iter().map(|w| (w.start, w.len)).collect();
+        assert_eq!(spans, vec![(0, 3), (2, 3), (6, 2)]);
+        assert_eq!(windows[1].direction, RunDirection::Decreasing);
+
+        let (longest, count) = longest_safe_window([5, 5, 6, 7, 8, 1], &policy);
+        assert_eq!(count, 1);
+        assert_eq!(
+            longest,
+            Some(Window {
+                start: 1,
+                len: 4,
+                direction: RunDirection::Increasing
+            })
+        );
+        assert_eq!(longest_safe_window([4], &policy), (None, 0));
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut rng = Rng::new(0x0bad_5eed_1234_5678);
+        let mut next = |bound: u64| rng.below(bound);
+        let policies = [
+            SafetyPolicy::default(),
+            SafetyPolicy {
+                allow_equal: true,
+                ..SafetyPolicy::default()
+            },
+            SafetyPolicy {
+                min_step: 2,
+                direction: Direction::Increasing,
+                ..SafetyPolicy::default()
+            },
+        ];
+
+        for _ in 0..3_000 {
+            let len = next(12) as usize;
+            let readings: Vec<i32> = (0..len).map(|_| next(6) as i32).collect();
+            for policy in &policies {
+                let windows: Vec<Window> = safe_windows(readings.iter().copied(), policy).collect();
+                let mut spans: Vec<(usize, usize)> =
+                    windows.iter().map(|w| (w.start, w.len)).collect();
+                spans.sort_unstable();
+                assert_eq!(spans, brute_force(&readings, policy), "{:?}", readings);
+
+                for w in windows {
+                    let slice = &readings[w.start..w.start + w.len];
+                    let flat = slice.windows(2).all(|p| p[0] == p[1]);
+                    assert_eq!(flat, w.direction == RunDirection::Flat, "{:?}", slice);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_report_reader() {
+        let input = "1 2  3\n\n7 x 9\n4 5";
+        let mut reports = ReportReader::new(input.as_bytes());
+        let mut seen = Vec::new();
+        while let Some(mut levels) = reports.next_report().unwrap() {
+            let values: Vec<i32> = levels.by_ref().collect();
+            let error = levels.finish().map(|e| match e {
+                LevelError::Invalid(e) => (e.line, e.column, e.token),
+                LevelError::Io(e) => panic!("{}", e),
+            });
+            seen.push((values, error));
+        }
+        assert_eq!(
+            seen,
+            vec![
+                (vec![1, 2, 3], None),
+                (vec![], None),
+                (vec![7], Some((3, 2, "x".to_string()))),
+                (vec![4, 5], None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,35 @@
+//! Oracles shared by the property tests, plus the seeded [`Rng`] they draw
+//! inputs from.
+
+use crate::levels_safe;
+use crate::policy::SafetyPolicy;
+
+pub use crate::generator::Rng;
+
+/// Smallest number of removals that leaves `readings` safe, found by checking
+/// every subset with `levels_safe`.
+pub fn subset_min_removals(readings: &[i32], policy: &SafetyPolicy) -> Option<usize> {
+    (0u32..1 << readings.len())
+        .filter_map(|mask| {
+            let kept: Vec<i32> = (0..readings.len())
+                .filter(|&i| mask & (1 << i) == 0)
+                .map(|i| readings[i])
+                .collect();
+            levels_safe(&kept, policy).then(|| mask.count_ones() as usize)
+        })
+        .min()
+}
+
+/// Tries every single removal through `levels_safe`.
+pub fn single_removal_safe(readings: &[i32], policy: &SafetyPolicy) -> bool {
+    levels_safe(readings, policy)
+        || (0..readings.len()).any(|skip| {
+            let removed: Vec<i32> = readings
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| i != skip)
+                .map(|(_, &level)| level)
+                .collect();
+            levels_safe(&removed, policy)
+        })
+}
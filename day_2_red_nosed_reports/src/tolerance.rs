@@ -0,0 +1,152 @@
+//! "Safe after removing at most k levels", generalising the Problem Dampener.
+//!
+//! For each allowed direction a DP finds the longest subsequence whose
+//! consecutive kept levels all satisfy the policy; everything else is what has
+//! to be removed. With a removal budget `k`, a kept level's predecessor can be
+//! at most `k + 1` positions back, so the DP costs O(n * k).
+
+use crate::policy::SafetyPolicy;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Removal {
+    /// Indices of the levels to remove, ascending.
+    pub removed: Vec<usize>,
+    /// Direction the remaining levels follow.
+    pub increasing: bool,
+}
+
+impl Removal {
+    pub fn count(&self) -> usize {
+        self.removed.len()
+    }
+}
+
+/// Longest valid subsequence in one direction using at most `limit` removals.
+fn longest_valid(
+    readings: &[i32],
+    policy: &SafetyPolicy,
+    increasing: bool,
+    limit: usize,
+) -> Option<Removal> {
+    let n = readings.len();
+    // len[i]: longest valid subsequence ending at i (None if it would need more
+    // than `limit` removals before i); parent[i]: previous kept level.
+    let mut len: Vec<Option<usize>> = vec![None; n];
+    let mut parent = vec![usize::MAX; n];
+
+    for i in 0..n {
+        if i <= limit {
+            len[i] = Some(1);
+        }
+        for j in i.saturating_sub(limit + 1)..i {
+            let Some(l) = len[j] else { continue };
+            // Levels between j and i are dropped, as are the j + 1 - l before j.
+            let removed = (i - j - 1) + (j + 1 - l);
+            if removed <= limit
+                && len[i].is_none_or(|best| l + 1 > best)
+                && policy.step_ok(readings[j], readings[i], increasing)
+            {
+                len[i] = Some(l + 1);
+                parent[i] = j;
+            }
+        }
+    }
+
+    let (end, kept) = (n.saturating_sub(limit + 1)..n)
+        .filter_map(|i| len[i].map(|l| (i, l)))
+        .filter(|&(_, l)| l >= 2 && n - l <= limit)
+        .max_by_key(|&(i, l)| (l, std::cmp::Reverse(i)))?;
+
+    let mut keep = vec![false; n];
+    let mut i = end;
+    for _ in 0..kept {
+        keep[i] = true;
+        i = parent[i];
+    }
+    Some(Removal {
+        removed: (0..n).filter(|&i| !keep[i]).collect(),
+        increasing,
+    })
+}
+
+/// Fewest levels to remove so the report is safe, or `None` if no subsequence
+/// of two or more levels is safe.
+pub fn min_removals(readings: &[i32], policy: &SafetyPolicy) -> Option<Removal> {
+    safe_within(readings, policy, readings.len())
+}
+
+/// The fewest removals needed, provided that is at most `k`.
+pub fn safe_within(readings: &[i32], policy: &SafetyPolicy, k: usize) -> Option<Removal> {
+    policy
+        .directions()
+        .iter()
+        .filter_map(|&increasing| longest_valid(readings, policy, increasing, k))
+        .min_by_key(Removal::count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Direction;
+    use crate::testutil::{subset_min_removals, Rng};
+    use crate::{levels_safe, levels_safe_dampened};
+
+    #[test]
+    fn test_min_removals() {
+        let policy = SafetyPolicy::default();
+        let removal = min_removals(&[1, 2, 9, 3, 8, 4], &policy).unwrap();
+        assert_eq!(removal.removed, vec![2, 4]);
+        assert!(removal.increasing);
+        assert_eq!(min_removals(&[7, 6, 4, 2, 1], &policy).unwrap().count(), 0);
+        assert_eq!(min_removals(&[5, 5, 5], &policy), None);
+        assert_eq!(safe_within(&[1, 2, 9, 3, 8, 4], &policy, 1), None);
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut rng = Rng::new(0x1234_5678_9abc_def1);
+        let mut next = |bound: u64| rng.below(bound);
+        let policies = [
+            SafetyPolicy::default(),
+            SafetyPolicy {
+                min_step: 2,
+                max_step: 5,
+                direction: Direction::Increasing,
+                allow_equal: true,
+            },
+        ];
+
+        for _ in 0..3_000 {
+            let len = next(10) as usize;
+            let mut level = 50;
+            let readings: Vec<i32> = (0..len)
+                .map(|_| {
+                    level += next(13) as i32 - 6;
+                    level
+                })
+                .collect();
+            for policy in &policies {
+                let removal = min_removals(&readings, policy);
+                assert_eq!(
+                    removal.as_ref().map(Removal::count),
+                    subset_min_removals(&readings, policy),
+                    "{:?}",
+                    readings
+                );
+                if let Some(removal) = removal {
+                    let kept: Vec<i32> = (0..readings.len())
+                        .filter(|i| !removal.removed.contains(i))
+                        .map(|i| readings[i])
+                        .collect();
+                    assert!(levels_safe(&kept, policy));
+                }
+                assert_eq!(
+                    safe_within(&readings, policy, 1).is_some(),
+                    levels_safe_dampened(&readings, policy),
+                    "{:?}",
+                    readings
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! Decimal readings with optional unit suffixes, such as `12.5mV`.
+//!
+//! Readings and step thresholds are rescaled onto one integer grid, the finest
+//! decimal resolution any of them uses, so the integer rules in this crate
+//! apply unchanged and comparisons stay exact. `12.5mV` and `0.0125V` are the
+//! same reading; a bare number is taken to be in the thresholds' unit, with no
+//! prefix, so `0.0185` next to `0.5mV..3mV` thresholds means volts.
+
+use crate::policy::{Direction, SafetyPolicy};
+use crate::{Check, InvalidLevel, Verdict};
+use std::fmt;
+use std::str::FromStr;
+
+/// SI prefixes recognised in front of a unit symbol.
+const PREFIXES: [(char, i32); 6] = [
+    ('n', -9),
+    ('u', -6),
+    ('µ', -6),
+    ('m', -3),
+    ('k', 3),
+    ('M', 6),
+];
+
+/// An exact decimal quantity: `mantissa * 10^exponent` of `unit`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quantity {
+    pub mantissa: i64,
+    pub exponent: i32,
+    /// Unit symbol without its prefix; empty for a bare number.
+    pub unit: String,
+}
+
+impl Quantity {
+    /// The value as a whole number of `10^exponent` units, if it is one and
+    /// fits in an `i64`.
+    fn at_exponent(&self, exponent: i32) -> Option<i64> {
+        if self.mantissa == 0 {
+            return Some(0);
+        }
+        let shift = u32::try_from(self.exponent.checked_sub(exponent)?).ok()?;
+        self.mantissa.checked_mul(10i64.checked_pow(shift)?)
+    }
+}
+
+/// Splits a suffix such as `mV` into its prefix exponent and unit. A single
+/// letter is always a unit, so `m` is metres rather than a bare "milli".
+fn split_prefix(suffix: &str) -> (i32, &str) {
+    let mut chars = suffix.chars();
+    if let Some(first) = chars.next() {
+        let rest = chars.as_str();
+        if let Some(&(_, exponent)) = PREFIXES.iter().find(|(p, _)| *p == first) {
+            if !rest.is_empty() {
+                return (exponent, rest);
+            }
+        }
+    }
+    (0, suffix)
+}
+
+impl FromStr for Quantity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let end = s
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+            .unwrap_or(s.len());
+        let (number, suffix) = s.split_at(end);
+        let (negative, digits) = match number.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, number.strip_prefix('+').unwrap_or(number)),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let is_digits = |d: &str| d.bytes().all(|b| b.is_ascii_digit());
+        if whole.len() + fraction.len() == 0 || !is_digits(whole) || !is_digits(fraction) {
+            return Err(format!("`{}` is not a decimal number", s));
+        }
+
+        let mut mantissa: i64 = format!("{}{}", whole, fraction)
+            .parse()
+            .map_err(|_| format!("`{}` has too many digits", s))?;
+        if negative {
+            mantissa = -mantissa;
+        }
+        let (prefix, unit) = split_prefix(suffix);
+        let mut exponent = prefix - fraction.len() as i32;
+        while mantissa != 0 && mantissa % 10 == 0 {
+            mantissa /= 10;
+            exponent += 1;
+        }
+        Ok(Quantity {
+            mantissa,
+            exponent,
+            unit: unit.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}e{}{}", self.mantissa, self.exponent, self.unit)
+    }
+}
+
+/// Step thresholds in physical units, e.g. `0.5mV..3mV`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitPolicy {
+    pub min_step: Quantity,
+    pub max_step: Quantity,
+    pub direction: Direction,
+    pub allow_equal: bool,
+}
+
+impl UnitPolicy {
+    /// Parses `min..max`, taking direction and equal steps from `base`.
+    pub fn parse(range: &str, base: &SafetyPolicy) -> Result<UnitPolicy, String> {
+        let (min, max) = range
+            .split_once("..")
+            .ok_or_else(|| format!("expected `min..max`, got `{}`", range))?;
+        let min_step: Quantity = min.trim().parse()?;
+        let max_step: Quantity = max.trim().parse()?;
+        if min_step.unit != max_step.unit {
+            return Err(format!(
+                "thresholds use different units, `{}` and `{}`",
+                min_step.unit, max_step.unit
+            ));
+        }
+
+        let exponent = min_step.exponent.min(max_step.exponent);
+        let (lo, hi) = (
+            min_step.at_exponent(exponent),
+            max_step.at_exponent(exponent),
+        );
+        if !matches!((lo, hi), (Some(lo), Some(hi)) if 0 < lo && lo <= hi) {
+            return Err(format!("needs 0 < min <= max, got `{}`", range));
+        }
+        Ok(UnitPolicy {
+            min_step,
+            max_step,
+            direction: base.direction,
+            allow_equal: base.allow_equal,
+        })
+    }
+
+    pub fn unit(&self) -> &str {
+        &self.min_step.unit
+    }
+}
+
+/// Parses a report of unit-aware readings and rescales it, with the policy's
+/// thresholds, onto a shared integer grid. Tokens that are not numbers, use a
+/// different unit, or do not fit the grid are reported as invalid levels.
+pub fn normalize_levels(
+    line: &str,
+    line_num: usize,
+    policy: &UnitPolicy,
+) -> Result<(Vec<i32>, SafetyPolicy), InvalidLevel> {
+    let invalid = |token: &str| InvalidLevel {
+        line: line_num,
+        column: token.as_ptr() as usize - line.as_ptr() as usize,
+        token: token.to_string(),
+    };
+
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let quantities = tokens
+        .iter()
+        .map(|&token| match token.parse::<Quantity>() {
+            Ok(q) if q.unit.is_empty() || q.unit == policy.unit() => Ok(q),
+            _ => Err(invalid(token)),
+        })
+        .collect::<Result<Vec<Quantity>, InvalidLevel>>()?;
+
+    // The finest reading decides the grid unless a threshold is finer still.
+    let finest = (0..quantities.len())
+        .filter(|&i| quantities[i].mantissa != 0)
+        .min_by_key(|&i| quantities[i].exponent);
+    let exponent = finest
+        .map_or(i32::MAX, |i| quantities[i].exponent)
+        .min(policy.min_step.exponent)
+        .min(policy.max_step.exponent);
+    let scale = |q: &Quantity| q.at_exponent(exponent).and_then(|v| i32::try_from(v).ok());
+
+    let readings = quantities
+        .iter()
+        .zip(&tokens)
+        .map(|(q, token)| scale(q).ok_or_else(|| invalid(token)))
+        .collect::<Result<Vec<i32>, InvalidLevel>>()?;
+    let (Some(min_step), Some(max_step)) = (scale(&policy.min_step), scale(&policy.max_step))
+    else {
+        // Only a very fine reading can push the thresholds off the grid.
+        return Err(invalid(tokens[finest.unwrap_or(0)]));
+    };
+
+    let policy = SafetyPolicy {
+        min_step,
+        max_step,
+        direction: policy.direction,
+        allow_equal: policy.allow_equal,
+    };
+    Ok((readings, policy))
+}
+
+pub fn classify_units(line: &str, line_num: usize, policy: &UnitPolicy, check: Check) -> Verdict {
+    match normalize_levels(line, line_num, policy) {
+        Ok((readings, policy)) if check.passes(&readings, &policy) => Verdict::Safe,
+        Ok(_) => Verdict::Unsafe,
+        Err(e) => Verdict::Invalid(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millivolts(range: &str) -> UnitPolicy {
+        UnitPolicy::parse(range, &SafetyPolicy::default()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_quantity() {
+        let q: Quantity = "12.5mV".parse().unwrap();
+        assert_eq!((q.mantissa, q.exponent, q.unit.as_str()), (125, -4, "V"));
+        let q: Quantity = "-0.0125V".parse().unwrap();
+        assert_eq!((q.mantissa, q.exponent, q.unit.as_str()), (-125, -4, "V"));
+        let q: Quantity = "300".parse().unwrap();
+        assert_eq!((q.mantissa, q.exponent, q.unit.as_str()), (3, 2, ""));
+        let q: Quantity = "2m".parse().unwrap();
+        assert_eq!((q.mantissa, q.exponent, q.unit.as_str()), (2, 0, "m"));
+        assert!("mV".parse::<Quantity>().is_err());
+        assert!("1.2.3V".parse::<Quantity>().is_err());
+        assert!("1-2".parse::<Quantity>().is_err());
+    }
+
+    #[test]
+    fn test_unit_policy() {
+        let policy = millivolts("0.5mV..3mV");
+        assert_eq!(policy.unit(), "V");
+        assert!(UnitPolicy::parse("1mV..3mA", &SafetyPolicy::default()).is_err());
+        assert!(UnitPolicy::parse("3mV..1mV", &SafetyPolicy::default()).is_err());
+        assert!(UnitPolicy::parse("0mV..1mV", &SafetyPolicy::default()).is_err());
+        assert!(UnitPolicy::parse("1mV", &SafetyPolicy::default()).is_err());
+    }
+
+    #[test]
+    fn test_classify_units() {
+        let policy = millivolts("0.5mV..3mV");
+        let check = Check::Plain;
+        assert_eq!(
+            classify_units("12.5mV 13mV 0.0155V 0.0185", 1, &policy, check),
+            Verdict::Safe
+        );
+        assert_eq!(
+            classify_units("12.5mV 12.7mV 13mV", 1, &policy, check),
+            Verdict::Unsafe
+        );
+        assert_eq!(
+            classify_units("12.5mV 16mV 17mV", 1, &policy, check),
+            Verdict::Unsafe
+        );
+        assert_eq!(
+            classify_units("12.5mV 16mV 17mV", 1, &policy, Check::Dampened),
+            Verdict::Safe
+        );
+        assert_eq!(
+            classify_units("12.5mV 13mA", 4, &policy, check),
+            Verdict::Invalid(InvalidLevel {
+                line: 4,
+                column: 7,
+                token: "13mA".to_string(),
+            })
+        );
+        assert!(matches!(
+            classify_units("1mV 1.0000000001mV", 1, &policy, check),
+            Verdict::Invalid(_)
+        ));
+    }
+
+    #[test]
+    fn test_integer_reports_unchanged() {
+        let policy = millivolts("1..3");
+        for line in ["7 6 4 2 1", "1 2 7 8 9", "1 3 2 4 5", "1 3 6 7 9"] {
+            assert_eq!(
+                classify_units(line, 1, &policy, Check::Plain),
+                crate::classify(line, 1, &SafetyPolicy::default(), Check::Plain)
+            );
+        }
+    }
+}
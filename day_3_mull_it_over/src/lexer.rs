@@ -0,0 +1,226 @@
+//! Hand-written lexer for corrupted memory.
+//!
+//! The input is split into three kinds of token, covering every byte exactly
+//! once:
+//!
+//! - a [`TokenKind::Name`] for each known instruction name (`mul`, `do`,
+//!   `don't`), wherever it appears, even inside a longer word;
+//! - a [`TokenKind::Args`] for a well-formed argument list such as `(2,4)` or
+//!   `()`, only directly after a name;
+//! - a [`TokenKind::Junk`] for each maximal run of anything else.
+//!
+//! Argument lists accept digit runs of any length; deciding which of them make
+//! a valid instruction is left to the parser.
+
+/// Known instruction names, longest first so `don't` wins over `do`.
+pub const NAMES: [&str; 3] = ["don't", "mul", "do"];
+
+/// Where a token sits in the input: byte offsets `start..end`, and the 1-based
+/// line and column (in characters) of its first character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenKind<'a> {
+    Name(&'a str),
+    /// The digit runs between the parentheses, in order.
+    Args(Vec<&'a str>),
+    Junk,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind<'a>,
+    pub text: &'a str,
+    pub span: Span,
+}
+
+pub struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+    line: usize,
+    column: usize,
+    after_name: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Lexer {
+            src,
+            pos: 0,
+            line: 1,
+            column: 1,
+            after_name: false,
+        }
+    }
+
+    fn name_at(&self, pos: usize) -> Option<&'a str> {
+        NAMES
+            .iter()
+            .find(|name| self.src[pos..].starts_with(*name))
+            .map(|name| &self.src[pos..pos + name.len()])
+    }
+
+    /// Length of the argument list at the current position, and its digit runs.
+    fn args_at(&self) -> Option<(usize, Vec<&'a str>)> {
+        let rest = self.src[self.pos..].strip_prefix('(')?;
+        let len = rest
+            .bytes()
+            .take_while(|b| b.is_ascii_digit() || *b == b',')
+            .count();
+        if !rest[len..].starts_with(')') {
+            return None;
+        }
+        let inner = &rest[..len];
+        let args: Vec<&str> = if inner.is_empty() {
+            Vec::new()
+        } else {
+            inner.split(',').collect()
+        };
+        args.iter()
+            .all(|a| !a.is_empty())
+            .then_some((len + 2, args))
+    }
+
+    /// Moves past `len` bytes, keeping the line and column up to date.
+    fn advance(&mut self, len: usize) -> Span {
+        let start = self.pos;
+        let (line, column) = (self.line, self.column);
+        for c in self.src[start..start + len].chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+        self.pos += len;
+        Span {
+            start,
+            end: self.pos,
+            line,
+            column,
+        }
+    }
+
+    fn token(&mut self, kind: TokenKind<'a>, len: usize) -> Token<'a> {
+        let span = self.advance(len);
+        Token {
+            kind,
+            text: &self.src[span.start..span.end],
+            span,
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        if self.pos == self.src.len() {
+            return None;
+        }
+        let after_name = std::mem::replace(&mut self.after_name, false);
+
+        if after_name {
+            if let Some((len, args)) = self.args_at() {
+                return Some(self.token(TokenKind::Args(args), len));
+            }
+        }
+        if let Some(name) = self.name_at(self.pos) {
+            self.after_name = true;
+            return Some(self.token(TokenKind::Name(name), name.len()));
+        }
+
+        let len = self.src[self.pos..]
+            .char_indices()
+            .skip(1)
+            .map(|(i, _)| i)
+            .find(|&i| self.name_at(self.pos + i).is_some())
+            .unwrap_or(self.src.len() - self.pos);
+        Some(self.token(TokenKind::Junk, len))
+    }
+}
+
+pub fn tokenize(src: &str) -> Vec<Token<'_>> {
+    Lexer::new(src).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(src: &str) -> Vec<(TokenKind<'_>, &str)> {
+        tokenize(src)
+            .into_iter()
+            .map(|t| (t.kind, t.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_example() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("xmul(2,4)&mul[3,7]!^don't()_mul(5,5)"),
+            vec![
+                (Junk, "x"),
+                (Name("mul"), "mul"),
+                (Args(vec!["2", "4"]), "(2,4)"),
+                (Junk, "&"),
+                (Name("mul"), "mul"),
+                (Junk, "[3,7]!^"),
+                (Name("don't"), "don't"),
+                (Args(vec![]), "()"),
+                (Junk, "_"),
+                (Name("mul"), "mul"),
+                (Args(vec!["5", "5"]), "(5,5)"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_args_only_after_name() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("(1,2)undo()mul (1,2)mul(1,,2)mul(1234,5)"),
+            vec![
+                (Junk, "(1,2)un"),
+                (Name("do"), "do"),
+                (Args(vec![]), "()"),
+                (Name("mul"), "mul"),
+                (Junk, " (1,2)"),
+                (Name("mul"), "mul"),
+                (Junk, "(1,,2)"),
+                (Name("mul"), "mul"),
+                (Args(vec!["1234", "5"]), "(1234,5)"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spans_cover_input() {
+        let src = "ab\nmul(1,2)é\n\ndo()";
+        let tokens = tokenize(src);
+        let mut end = 0;
+        for t in &tokens {
+            assert_eq!(t.span.start, end);
+            assert_eq!(&src[t.span.start..t.span.end], t.text);
+            end = t.span.end;
+        }
+        assert_eq!(end, src.len());
+
+        let positions: Vec<(usize, usize)> = tokens
+            .iter()
+            .map(|t| (t.span.line, t.span.column))
+            .collect();
+        assert_eq!(
+            positions,
+            vec![(1, 1), (2, 1), (2, 4), (2, 9), (4, 1), (4, 3)]
+        );
+    }
+}
@@ -0,0 +1,185 @@
+pub mod lexer;
+
+use lexer::{Lexer, Span, Token, TokenKind};
+use std::fmt;
+use std::iter::Peekable;
+
+/// Largest number of digits a `mul` argument may have.
+pub const MAX_ARG_DIGITS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Mul(i64, i64),
+    Do,
+    Dont,
+}
+
+/// A name followed by an argument list that does not make a valid instruction,
+/// such as `mul(1,2,3)` or `do(4)`. Such text is ignored, as it always was, but
+/// is worth pointing at when the corrupted memory is being inspected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub span: Span,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.span.line, self.span.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Program {
+    /// Valid instructions in input order, each spanning its name and arguments.
+    pub instructions: Vec<(Instruction, Span)>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+fn instruction(name: &str, args: &[&str]) -> Result<Instruction, String> {
+    match (name, args) {
+        ("mul", [a, b]) => match args.iter().find(|a| a.len() > MAX_ARG_DIGITS) {
+            Some(long) => Err(format!(
+                "`mul` argument `{}` has more than {} digits",
+                long, MAX_ARG_DIGITS
+            )),
+            None => Ok(Instruction::Mul(a.parse().unwrap(), b.parse().unwrap())),
+        },
+        ("mul", _) => Err(format!("`mul` takes 2 arguments, got {}", args.len())),
+        ("do", []) => Ok(Instruction::Do),
+        ("don't", []) => Ok(Instruction::Dont),
+        (name, _) => Err(format!("`{}` takes no arguments, got {}", name, args.len())),
+    }
+}
+
+fn next_args<'a, I: Iterator<Item = Token<'a>>>(tokens: &mut Peekable<I>) -> Option<Token<'a>> {
+    tokens.next_if(|t| matches!(t.kind, TokenKind::Args(_)))
+}
+
+pub fn parse(src: &str) -> Program {
+    let mut program = Program::default();
+    let mut tokens = Lexer::new(src).peekable();
+
+    while let Some(token) = tokens.next() {
+        let TokenKind::Name(name) = token.kind else {
+            continue;
+        };
+        // A name on its own, like the `mul` in `mul[3,7]`, is just junk.
+        let Some(Token {
+            kind: TokenKind::Args(args),
+            span: args_span,
+            ..
+        }) = next_args(&mut tokens)
+        else {
+            continue;
+        };
+
+        let span = Span {
+            end: args_span.end,
+            ..token.span
+        };
+        match instruction(name, &args) {
+            Ok(instruction) => program.instructions.push((instruction, span)),
+            Err(message) => program.diagnostics.push(Diagnostic { span, message }),
+        }
+    }
+    program
+}
+
+/// Every `mul` instruction's arguments, ignoring `do()` and `don't()`.
+pub fn muls(instructions: &[(Instruction, Span)]) -> impl Iterator<Item = (i64, i64, Span)> + '_ {
+    instructions
+        .iter()
+        .filter_map(|&(instruction, span)| match instruction {
+            Instruction::Mul(a, b) => Some((a, b, span)),
+            _ => None,
+        })
+}
+
+/// The `mul` instructions that are enabled: not after a `don't()` unless a
+/// later `do()` has switched them back on.
+pub fn enabled_muls(
+    instructions: &[(Instruction, Span)],
+) -> impl Iterator<Item = (i64, i64, Span)> + '_ {
+    instructions
+        .iter()
+        .scan(true, |enabled, &(instruction, span)| {
+            Some(match instruction {
+                Instruction::Do => {
+                    *enabled = true;
+                    None
+                }
+                Instruction::Dont => {
+                    *enabled = false;
+                    None
+                }
+                Instruction::Mul(a, b) => enabled.then_some((a, b, span)),
+            })
+        })
+        .flatten()
+}
+
+pub fn sum_products(src: &str) -> i64 {
+    muls(&parse(src).instructions).map(|(a, b, _)| a * b).sum()
+}
+
+pub fn sum_enabled_products(src: &str) -> i64 {
+    enabled_muls(&parse(src).instructions)
+        .map(|(a, b, _)| a * b)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
+    const EXAMPLE_2: &str =
+        "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+
+    #[test]
+    fn test_sums() {
+        assert_eq!(sum_products(EXAMPLE), 161);
+        assert_eq!(sum_enabled_products(EXAMPLE_2), 48);
+        assert_eq!(sum_products("mul(1,2)\nmul(3,4)"), 14);
+        assert_eq!(sum_enabled_products("don't()\nmul(3,4)\ndo()mul(1,2)"), 2);
+    }
+
+    #[test]
+    fn test_parse() {
+        let program = parse("mul(2,3)\n  don't()do()");
+        let instructions: Vec<Instruction> = program.instructions.iter().map(|i| i.0).collect();
+        assert_eq!(
+            instructions,
+            vec![Instruction::Mul(2, 3), Instruction::Dont, Instruction::Do]
+        );
+        let dont = program.instructions[1].1;
+        assert_eq!(
+            (dont.start, dont.end, dont.line, dont.column),
+            (11, 18, 2, 3)
+        );
+        assert!(program.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let program = parse("mul(1234,5)mul(1,2,3)\ndo(4)mul()mul[1,2]");
+        let messages: Vec<String> = program.diagnostics.iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "line 1, column 1: `mul` argument `1234` has more than 3 digits",
+                "line 1, column 12: `mul` takes 2 arguments, got 3",
+                "line 2, column 1: `do` takes no arguments, got 1",
+                "line 2, column 6: `mul` takes 2 arguments, got 0",
+            ]
+        );
+        assert!(program.instructions.is_empty());
+    }
+}
@@ -1,30 +1,25 @@
-use regex::Regex;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use day_3_mull_it_over::{muls, parse};
+use std::fs;
 
 fn main() -> std::io::Result<()> {
-    let file = File::open("input")?;
-    let reader = BufReader::new(file);
+    let content = fs::read_to_string("input")?;
+    let diagnostics = std::env::args().any(|a| a == "--diagnostics");
 
-    let re = Regex::new(r"mul\(([0-9]{1,3})\,([0-9]{1,3})\)").unwrap();
+    let program = parse(&content);
+    if diagnostics {
+        for diagnostic in &program.diagnostics {
+            eprintln!("{}", diagnostic);
+        }
+    }
 
+    let mut products = muls(&program.instructions).peekable();
     let mut total = 0;
-    for line in reader.lines() {
-        match line {
-            Ok(line) => {
-                println!("line: {}", line);
-                for caps in re.captures_iter(&line) {
-                    match (caps[1].parse::<i32>(), caps[2].parse::<i32>()) {
-                        (Ok(first), Ok(second)) => {
-                            let product = first * second;
-                            println!("{}  × {} = {}", first, second, product);
-                            total += product;
-                        }
-                        _ => eprintln!("error parsing numbers: {}", &caps[0]),
-                    }
-                }
-            }
-            Err(e) => eprintln!("error reading line: {}", e),
+    for (i, line) in content.lines().enumerate() {
+        println!("line: {}", line);
+        while let Some((first, second, _)) = products.next_if(|(_, _, span)| span.line == i + 1) {
+            let product = first * second;
+            println!("{}  × {} = {}", first, second, product);
+            total += product;
         }
     }
 